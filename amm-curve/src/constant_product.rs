@@ -0,0 +1,228 @@
+//! x * y = k pools. Every quote rounds against the caller so the invariant and
+//! the value of existing LP shares can only grow.
+
+use crate::{div, isqrt, mul_div, to_u64, CurveError, Rounding, BPS_DENOMINATOR};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapQuote {
+    pub amount_out : u64,
+    // part of the input kept by the pool as the fee
+    pub fee : u64,
+}
+
+// token x and y owed for minting `lp` out of `supply` , round up when the pool receives them
+pub fn deposit_amounts(
+    lp : u64 ,
+    supply : u64 ,
+    reserve_x : u64 ,
+    reserve_y : u64 ,
+    rounding : Rounding
+) -> Result<(u64, u64), CurveError> {
+    Ok((
+        mul_div(lp, reserve_x, supply, rounding)?,
+        mul_div(lp, reserve_y, supply, rounding)?,
+    ))
+}
+
+// token x and y paid out for burning `lp` out of `supply` , round down when the pool pays them
+pub fn withdraw_amounts(
+    lp : u64 ,
+    supply : u64 ,
+    reserve_x : u64 ,
+    reserve_y : u64 ,
+    rounding : Rounding
+) -> Result<(u64, u64), CurveError> {
+    deposit_amounts(lp, supply, reserve_x, reserve_y, rounding)
+}
+
+// LP minted for depositing `x` and `y` , the smaller side sets it so an unbalanced deposit donates the excess
+pub fn lp_for_deposit(
+    x : u64 ,
+    y : u64 ,
+    supply : u64 ,
+    reserve_x : u64 ,
+    reserve_y : u64
+) -> Result<u64, CurveError> {
+    if supply == 0 {
+        return initial_lp(x, y);
+    }
+    let from_x = mul_div(x, supply, reserve_x, Rounding::Down)?;
+    let from_y = mul_div(y, supply, reserve_y, Rounding::Down)?;
+    Ok(from_x.min(from_y))
+}
+
+// LP a pool's first deposit locks away for good , so the share price can't be pushed up until later
+// deposits round down to nothing
+pub const MINIMUM_LIQUIDITY: u64 = 1_000;
+
+// LP supply of a pool's first deposit , the geometric mean of both sides
+pub fn initial_lp(x : u64 , y : u64) -> Result<u64, CurveError> {
+    to_u64(isqrt(x as u128 * y as u128))
+}
+
+// output for selling `amount_in` , the fee is taken from the input and the output rounds down
+pub fn swap_out(
+    amount_in : u64 ,
+    reserve_in : u64 ,
+    reserve_out : u64 ,
+    fee_bps : u16
+) -> Result<SwapQuote, CurveError> {
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(CurveError::ZeroLiquidity);
+    }
+    if fee_bps as u128 >= BPS_DENOMINATOR {
+        return Err(CurveError::InvalidFee);
+    }
+    // the fee rounds up so dust trades can't skip it
+    let fee = div(amount_in as u128 * fee_bps as u128, BPS_DENOMINATOR, Rounding::Up)?;
+    let in_after_fee = amount_in as u128 - fee;
+    let amount_out = div(
+        in_after_fee * reserve_out as u128,
+        reserve_in as u128 + in_after_fee,
+        Rounding::Down,
+    )?;
+    Ok(SwapQuote { amount_out: to_u64(amount_out)?, fee: to_u64(fee)? })
+}
+
+// input needed to receive at least `amount_out` , rounded up , the inverse of `swap_out`
+pub fn swap_in(
+    amount_out : u64 ,
+    reserve_in : u64 ,
+    reserve_out : u64 ,
+    fee_bps : u16
+) -> Result<u64, CurveError> {
+    if reserve_in == 0 || amount_out >= reserve_out {
+        return Err(CurveError::ZeroLiquidity);
+    }
+    if fee_bps as u128 >= BPS_DENOMINATOR {
+        return Err(CurveError::InvalidFee);
+    }
+    let in_after_fee = div(
+        reserve_in as u128 * amount_out as u128,
+        (reserve_out - amount_out) as u128,
+        Rounding::Up,
+    )?;
+    let amount_in = div(
+        in_after_fee * BPS_DENOMINATOR,
+        BPS_DENOMINATOR - fee_bps as u128,
+        Rounding::Up,
+    )?;
+    to_u64(amount_in)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const MAX_FEE: u16 = 1_000;
+
+    fn reserve() -> impl Strategy<Value = u64> {
+        1..=u64::MAX / 4
+    }
+
+    proptest! {
+        #[test]
+        fn swap_never_shrinks_k(
+            amount_in in any::<u64>(),
+            reserve_in in reserve(),
+            reserve_out in reserve(),
+            fee in 0..=MAX_FEE,
+        ) {
+            let Ok(quote) = swap_out(amount_in, reserve_in, reserve_out, fee) else {
+                return Ok(());
+            };
+            prop_assert!(quote.amount_out < reserve_out);
+            let k_before = reserve_in as u128 * reserve_out as u128;
+            let in_after = reserve_in as u128 + amount_in as u128;
+            let out_after = (reserve_out - quote.amount_out) as u128;
+            // k can exceed u128 after the swap , compare without overflowing
+            prop_assert!(in_after.checked_mul(out_after).is_none_or(|k_after| k_after >= k_before));
+        }
+
+        #[test]
+        fn swap_out_is_monotonic(
+            a in any::<u32>(),
+            b in any::<u32>(),
+            reserve_in in reserve(),
+            reserve_out in reserve(),
+            fee in 0..=MAX_FEE,
+        ) {
+            let (small, large) = (a.min(b) as u64, a.max(b) as u64);
+            let small_out = swap_out(small, reserve_in, reserve_out, fee).unwrap().amount_out;
+            let large_out = swap_out(large, reserve_in, reserve_out, fee).unwrap().amount_out;
+            prop_assert!(small_out <= large_out);
+        }
+
+        #[test]
+        fn swap_in_covers_the_requested_output(
+            amount_out in 1..=u32::MAX as u64,
+            reserve_in in 1..=u64::MAX / 4,
+            reserve_out in (u32::MAX as u64 + 1)..=u64::MAX / 4,
+            fee in 0..=MAX_FEE,
+        ) {
+            let Ok(amount_in) = swap_in(amount_out, reserve_in, reserve_out, fee) else {
+                return Ok(());
+            };
+            if let Ok(quote) = swap_out(amount_in, reserve_in, reserve_out, fee) {
+                prop_assert!(quote.amount_out >= amount_out);
+            }
+        }
+
+        #[test]
+        fn deposit_then_withdraw_never_profits(
+            lp in 1..=u32::MAX as u64,
+            supply in 1..=u32::MAX as u64,
+            reserve_x in 1..=u32::MAX as u64,
+            reserve_y in 1..=u32::MAX as u64,
+        ) {
+            let (x, y) = deposit_amounts(lp, supply, reserve_x, reserve_y, Rounding::Up).unwrap();
+            let (out_x, out_y) = withdraw_amounts(
+                lp, supply + lp, reserve_x + x, reserve_y + y, Rounding::Down,
+            ).unwrap();
+            prop_assert!(out_x <= x && out_y <= y);
+        }
+
+        #[test]
+        fn lp_for_deposit_never_overpays(
+            x in 1..=u32::MAX as u64,
+            y in 1..=u32::MAX as u64,
+            supply in 1..=u32::MAX as u64,
+            reserve_x in 1..=u32::MAX as u64,
+            reserve_y in 1..=u32::MAX as u64,
+        ) {
+            let lp = lp_for_deposit(x, y, supply, reserve_x, reserve_y).unwrap();
+            let (owed_x, owed_y) = deposit_amounts(lp, supply, reserve_x, reserve_y, Rounding::Up).unwrap();
+            prop_assert!(owed_x <= x && owed_y <= y);
+        }
+    }
+
+    // small pools are where rounding bites , so walk every case instead of sampling
+    #[test]
+    fn small_pools_exhaustively() {
+        for reserve_in in 1..=24u64 {
+            for reserve_out in 1..=24u64 {
+                for amount_in in 0..=48u64 {
+                    for fee in [0, 30, MAX_FEE] {
+                        let quote = swap_out(amount_in, reserve_in, reserve_out, fee).unwrap();
+                        let k_after = (reserve_in + amount_in) * (reserve_out - quote.amount_out);
+                        assert!(k_after >= reserve_in * reserve_out);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn empty_pools_and_bad_fees_are_errors() {
+        assert_eq!(swap_out(1, 0, 1, 0), Err(CurveError::ZeroLiquidity));
+        assert_eq!(swap_out(1, 1, 1, 10_000), Err(CurveError::InvalidFee));
+        assert_eq!(swap_in(1, 1, 1, 0), Err(CurveError::ZeroLiquidity));
+        assert_eq!(withdraw_amounts(1, 0, 1, 1, Rounding::Down), Err(CurveError::ZeroLiquidity));
+    }
+
+    #[test]
+    fn first_deposit_mints_the_geometric_mean() {
+        assert_eq!(lp_for_deposit(1_000_000, 4_000_000, 0, 0, 0), Ok(2_000_000));
+    }
+}
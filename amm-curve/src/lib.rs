@@ -0,0 +1,107 @@
+//! Pool math for the `amm` program , kept free of `std` and solana types so
+//! the on-chain program and off-chain clients quote from the same code.
+#![no_std]
+
+pub mod constant_product;
+pub mod stable_swap;
+pub mod twap;
+pub mod zap;
+
+// constant product is the default curve , stable swap quotes are reached through `stable_swap::`
+pub use constant_product::*;
+
+pub const BPS_DENOMINATOR: u128 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurveError {
+    // a reserve or the LP supply the quote divides by is empty
+    ZeroLiquidity,
+    // the result doesn't fit in a u64
+    Overflow,
+    // fee at or above 100%
+    InvalidFee,
+    // stable swap amplification outside MIN_AMP..=MAX_AMP
+    InvalidAmp,
+    // newton's method didn't settle within its iteration limit
+    NoConvergence,
+    // a price average over zero seconds
+    EmptyWindow,
+}
+
+// which way a division leaves the remainder , always pick the one that favours the pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    Up,
+    Down,
+}
+
+pub fn div(numerator : u128 , denominator : u128 , rounding : Rounding) -> Result<u128, CurveError> {
+    if denominator == 0 {
+        return Err(CurveError::ZeroLiquidity);
+    }
+    Ok(match rounding {
+        Rounding::Up => numerator.div_ceil(denominator),
+        Rounding::Down => numerator / denominator,
+    })
+}
+
+pub fn to_u64(value : u128) -> Result<u64, CurveError> {
+    u64::try_from(value).map_err(|_| CurveError::Overflow)
+}
+
+// a * b / c with a u128 intermediate , a u64 product never overflows it
+pub fn mul_div(a : u64 , b : u64 , c : u64 , rounding : Rounding) -> Result<u64, CurveError> {
+    to_u64(div(a as u128 * b as u128, c as u128, rounding)?)
+}
+
+// floor of the square root , used for the LP supply of a pool's first deposit
+pub fn isqrt(value : u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+    // newton's method from above converges on the floor
+    let mut x = 1u128 << (128 - value.leading_zeros()).div_ceil(2);
+    loop {
+        let next = (x + value / x) / 2;
+        if next >= x {
+            return x;
+        }
+        x = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn mul_div_rounds_both_ways(a in any::<u64>(), b in any::<u64>(), c in 1..=u64::MAX) {
+            let exact = a as u128 * b as u128;
+            match (mul_div(a, b, c, Rounding::Down), mul_div(a, b, c, Rounding::Up)) {
+                (Ok(down), Ok(up)) => {
+                    prop_assert!(down as u128 * c as u128 <= exact);
+                    prop_assert!(up as u128 * c as u128 >= exact);
+                    prop_assert!(up - down <= 1);
+                }
+                (down, up) => {
+                    prop_assert!(exact.div_ceil(c as u128) > u64::MAX as u128);
+                    prop_assert!(down.is_err() || up.is_err());
+                }
+            }
+        }
+
+        #[test]
+        fn isqrt_is_the_floor(value in any::<u128>()) {
+            let root = isqrt(value);
+            prop_assert!(root * root <= value);
+            prop_assert!((root + 1).checked_mul(root + 1).is_none_or(|square| square > value));
+        }
+    }
+
+    #[test]
+    fn division_by_zero_is_an_error() {
+        assert_eq!(mul_div(1, 1, 0, Rounding::Down), Err(CurveError::ZeroLiquidity));
+    }
+}
@@ -0,0 +1,281 @@
+//! Curve StableSwap for two coins: A * n^n * (x + y) + D = A * D * n^n + D^(n+1) / (n^n * x * y).
+//! D and y have no closed form , both are found with Newton's method. Intermediates are checked u128,
+//! which covers pools whose combined reserves stay below roughly 2^60.
+
+use crate::{div, to_u64, CurveError, Rounding, SwapQuote, BPS_DENOMINATOR};
+
+pub const MIN_AMP: u64 = 1;
+pub const MAX_AMP: u64 = 1_000_000;
+
+const N_COINS: u128 = 2;
+const MAX_ITERATIONS: usize = 255;
+
+fn mul(a : u128 , b : u128) -> Result<u128, CurveError> {
+    a.checked_mul(b).ok_or(CurveError::Overflow)
+}
+
+fn add(a : u128 , b : u128) -> Result<u128, CurveError> {
+    a.checked_add(b).ok_or(CurveError::Overflow)
+}
+
+fn check_amp(amp : u64) -> Result<u128, CurveError> {
+    if !(MIN_AMP..=MAX_AMP).contains(&amp) {
+        return Err(CurveError::InvalidAmp);
+    }
+    Ok(amp as u128 * N_COINS)
+}
+
+// the invariant D of a pool holding `x` and `y`
+pub fn compute_d(amp : u64 , x : u64 , y : u64) -> Result<u128, CurveError> {
+    let ann = check_amp(amp)?;
+    let (x, y) = (x as u128, y as u128);
+    if x == 0 || y == 0 {
+        return Err(CurveError::ZeroLiquidity);
+    }
+
+    let sum = x + y;
+    let mut d = sum;
+    for _ in 0..MAX_ITERATIONS {
+        // d_p = D^3 / (n^n * x * y)
+        let d_p = mul(d, d)? / (x * N_COINS);
+        let d_p = mul(d_p, d)? / (y * N_COINS);
+        let previous = d;
+        let numerator = mul(add(mul(ann, sum)?, mul(d_p, N_COINS)?)?, d)?;
+        let denominator = add(mul(ann - 1, d)?, mul(N_COINS + 1, d_p)?)?;
+        d = numerator / denominator;
+        if d.abs_diff(previous) <= 1 {
+            return Ok(d);
+        }
+    }
+    Err(CurveError::NoConvergence)
+}
+
+// the other reserve that keeps the invariant at `d` once one side holds `x`
+pub fn compute_y(amp : u64 , x : u128 , d : u128) -> Result<u128, CurveError> {
+    let ann = check_amp(amp)?;
+    if x == 0 {
+        return Err(CurveError::ZeroLiquidity);
+    }
+
+    // y^2 + (b - D) * y = c , solved as y = (y^2 + c) / (2y + b - D)
+    let c = mul(d, d)? / (x * N_COINS);
+    let c = mul(c, d)? / mul(ann, N_COINS)?;
+    let b = add(x, d / ann)?;
+
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let previous = y;
+        let numerator = add(mul(y, y)?, c)?;
+        let denominator = add(mul(y, 2)?, b)?
+            .checked_sub(d)
+            .filter(|denominator| *denominator > 0)
+            .ok_or(CurveError::NoConvergence)?;
+        y = numerator / denominator;
+        if y.abs_diff(previous) <= 1 {
+            return Ok(y);
+        }
+    }
+    Err(CurveError::NoConvergence)
+}
+
+// output for selling `amount_in` , the fee comes off the input like on constant product pools
+pub fn swap_out(
+    amount_in : u64 ,
+    reserve_in : u64 ,
+    reserve_out : u64 ,
+    amp : u64 ,
+    fee_bps : u16
+) -> Result<SwapQuote, CurveError> {
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(CurveError::ZeroLiquidity);
+    }
+    if fee_bps as u128 >= BPS_DENOMINATOR {
+        return Err(CurveError::InvalidFee);
+    }
+    let fee = div(amount_in as u128 * fee_bps as u128, BPS_DENOMINATOR, Rounding::Up)?;
+    let in_after_fee = amount_in as u128 - fee;
+
+    let d = compute_d(amp, reserve_in, reserve_out)?;
+    let new_out = compute_y(amp, reserve_in as u128 + in_after_fee, d)?;
+    // newton stops within one unit of the root , keep that unit in the pool
+    let amount_out = (reserve_out as u128).saturating_sub(new_out).saturating_sub(1);
+    Ok(SwapQuote { amount_out: to_u64(amount_out)?, fee: to_u64(fee)? })
+}
+
+// keeps only the top bits of both so the numerator can be shifted into Q64.64 without overflowing
+fn ratio_q64(numerator : u128 , denominator : u128) -> Result<u128, CurveError> {
+    let shift = (128 - numerator.leading_zeros()).saturating_sub(64);
+    let denominator = denominator >> shift;
+    if denominator == 0 {
+        return Err(CurveError::Overflow);
+    }
+    Ok(((numerator >> shift) << 64) / denominator)
+}
+
+// Q64.64 price of one unit of the base token in the quote token at the pool's current point , the slope
+// -dy/dx of the invariant at `d` : (Ann * x * y / D + D^2 / 4x) / (Ann * x * y / D + D^2 / 4y) ,
+// which sits between the constant sum price of one and the constant product price y / x
+pub fn spot_price_q64(amp : u64 , d : u128 , reserve_base : u64 , reserve_quote : u64) -> Result<u128, CurveError> {
+    let ann = check_amp(amp)?;
+    let (x, y) = (reserve_base as u128, reserve_quote as u128);
+    if x == 0 || y == 0 || d == 0 {
+        return Err(CurveError::ZeroLiquidity);
+    }
+
+    // x * y fits , scaling it by Ann first keeps the precision unless the pool is near the 2^60 limit
+    let product = x * y;
+    let sum_term = match ann.checked_mul(product) {
+        Some(scaled) => scaled / d,
+        None => mul(ann, product / d)?,
+    };
+    let d_squared = mul(d, d)?;
+    let numerator = add(sum_term, d_squared / (x * N_COINS * N_COINS))?;
+    let denominator = add(sum_term, d_squared / (y * N_COINS * N_COINS))?;
+    ratio_q64(numerator, denominator)
+}
+
+// amplification of a pool ramping linearly from `initial` at `start_ts` to `target` at `stop_ts`
+pub fn ramp_amp(initial : u64 , target : u64 , start_ts : i64 , stop_ts : i64 , now : i64) -> u64 {
+    if now >= stop_ts {
+        return target;
+    }
+    if now <= start_ts {
+        return initial;
+    }
+    let elapsed = (now - start_ts) as u128;
+    let duration = (stop_ts - start_ts) as u128;
+    if target >= initial {
+        initial + ((target - initial) as u128 * elapsed / duration) as u64
+    } else {
+        initial - ((initial - target) as u128 * elapsed / duration) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constant_product;
+    use proptest::prelude::*;
+
+    const MAX_FEE: u16 = 1_000;
+    const Q64: u128 = 1 << 64;
+
+    fn reserve() -> impl Strategy<Value = u64> {
+        1_000..=1u64 << 48
+    }
+
+    proptest! {
+        #[test]
+        fn balanced_pools_have_d_equal_to_the_sum(amp in MIN_AMP..=MAX_AMP, reserve in reserve()) {
+            let d = compute_d(amp, reserve, reserve).unwrap();
+            prop_assert!(d.abs_diff(2 * reserve as u128) <= 1);
+        }
+
+        #[test]
+        fn swaps_never_shrink_d(
+            amp in MIN_AMP..=MAX_AMP,
+            reserve_in in reserve(),
+            reserve_out in reserve(),
+            amount_in in 0..=1u64 << 40,
+            fee in 0..=MAX_FEE,
+        ) {
+            let Ok(quote) = swap_out(amount_in, reserve_in, reserve_out, amp, fee) else {
+                return Ok(());
+            };
+            prop_assert!(quote.amount_out < reserve_out);
+            let d_before = compute_d(amp, reserve_in, reserve_out).unwrap();
+            let d_after = compute_d(amp, reserve_in + amount_in, reserve_out - quote.amount_out).unwrap();
+            // D itself is only found to within one unit
+            prop_assert!(d_after + 1 >= d_before);
+        }
+
+        #[test]
+        fn balanced_pools_beat_constant_product(
+            amp in MIN_AMP..=MAX_AMP,
+            reserve in reserve(),
+            share in 1..=10u64,
+            fee in 0..=MAX_FEE,
+        ) {
+            let amount_in = reserve / 100 * share;
+            let stable = swap_out(amount_in, reserve, reserve, amp, fee).unwrap();
+            let product = constant_product::swap_out(amount_in, reserve, reserve, fee).unwrap();
+            prop_assert!(stable.amount_out + 1 >= product.amount_out);
+            // and never pay out more than a constant sum pool would
+            prop_assert!(stable.amount_out <= amount_in - stable.fee);
+        }
+
+        #[test]
+        fn spot_price_sits_between_constant_sum_and_product(
+            amp in MIN_AMP..=MAX_AMP,
+            reserve_x in reserve(),
+            reserve_y in reserve(),
+        ) {
+            let d = compute_d(amp, reserve_x, reserve_y).unwrap();
+            let spot = spot_price_q64(amp, d, reserve_x, reserve_y).unwrap();
+            let product = crate::twap::price_q64(reserve_x, reserve_y).unwrap();
+            // both ends are only exact to the last bits the ratio keeps
+            let slack = spot >> 40;
+            prop_assert!(spot + slack >= product.min(Q64));
+            prop_assert!(spot <= product.max(Q64) + slack);
+        }
+
+        #[test]
+        fn small_swaps_never_beat_the_spot_price(
+            amp in MIN_AMP..=MAX_AMP,
+            reserve_in in 1u64 << 20..=1u64 << 40,
+            reserve_out in 1u64 << 20..=1u64 << 40,
+        ) {
+            // the curve is convex , so selling only gets worse than the price before the trade
+            let amount_in = reserve_in / 1_000;
+            let quote = swap_out(amount_in, reserve_in, reserve_out, amp, 0).unwrap();
+            let d = compute_d(amp, reserve_in, reserve_out).unwrap();
+            let spot = spot_price_q64(amp, d, reserve_in, reserve_out).unwrap();
+            let bound = spot * amount_in as u128;
+            prop_assert!(((quote.amount_out as u128) << 64) <= bound + (bound >> 16));
+        }
+
+        #[test]
+        fn ramp_stays_between_its_ends(
+            initial in MIN_AMP..=MAX_AMP,
+            target in MIN_AMP..=MAX_AMP,
+            start in 0..=1i64 << 40,
+            duration in 1..=1i64 << 30,
+            now in 0..=1i64 << 41,
+        ) {
+            let amp = ramp_amp(initial, target, start, start + duration, now);
+            prop_assert!(amp >= initial.min(target) && amp <= initial.max(target));
+        }
+    }
+
+    #[test]
+    fn small_pools_exhaustively() {
+        for amp in [1, 10, 100, 5_000] {
+            for reserve_in in 1..=24u64 {
+                for reserve_out in 1..=24u64 {
+                    for amount_in in 0..=24u64 {
+                        let Ok(quote) = swap_out(amount_in, reserve_in, reserve_out, amp, 30) else {
+                            continue;
+                        };
+                        assert!(quote.amount_out < reserve_out);
+                        assert!(quote.amount_out <= amount_in + reserve_out / 2);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn balanced_pools_price_at_one() {
+        for amp in [MIN_AMP, 100, MAX_AMP] {
+            let d = compute_d(amp, 1_000_000, 1_000_000).unwrap();
+            assert_eq!(spot_price_q64(amp, d, 1_000_000, 1_000_000), Ok(Q64));
+        }
+    }
+
+    #[test]
+    fn bad_amps_are_errors() {
+        assert_eq!(compute_d(0, 1, 1), Err(CurveError::InvalidAmp));
+        assert_eq!(compute_d(MAX_AMP + 1, 1, 1), Err(CurveError::InvalidAmp));
+        assert_eq!(compute_d(100, 0, 1), Err(CurveError::ZeroLiquidity));
+    }
+}
@@ -0,0 +1,80 @@
+//! Uniswap v2 style price accumulators. Each side of a pool sums `price * seconds` into a u128
+//! that is allowed to wrap , the difference between two readings divided by the seconds between
+//! them is the time weighted average price over that window.
+
+use crate::CurveError;
+
+// prices are Q64.64 fixed point , 1 << 64 is a price of exactly one
+pub const Q64: u128 = 1 << 64;
+
+// price of one unit of the base token in units of the quote token
+pub fn price_q64(reserve_base : u64 , reserve_quote : u64) -> Result<u128, CurveError> {
+    if reserve_base == 0 {
+        return Err(CurveError::ZeroLiquidity);
+    }
+    // a u64 shifted by 64 still fits , so this never overflows
+    Ok(((reserve_quote as u128) << 64) / reserve_base as u128)
+}
+
+// adds `price` held for `elapsed` seconds , wrapping is fine since only differences are ever read
+pub fn accumulate(cumulative : u128 , price : u128 , elapsed : u64) -> u128 {
+    cumulative.wrapping_add(price.wrapping_mul(elapsed as u128))
+}
+
+// average Q64.64 price between two readings of the same accumulator
+// stays exact across a wrap as long as the window's own sum fits in a u128
+pub fn average_price(
+    cumulative_start : u128 ,
+    timestamp_start : i64 ,
+    cumulative_end : u128 ,
+    timestamp_end : i64
+) -> Result<u128, CurveError> {
+    if timestamp_end <= timestamp_start {
+        return Err(CurveError::EmptyWindow);
+    }
+    Ok(cumulative_end.wrapping_sub(cumulative_start) / (timestamp_end - timestamp_start) as u128)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn a_steady_price_averages_to_itself(
+            reserve_base in 1..=u64::MAX,
+            reserve_quote in any::<u64>(),
+            start in any::<u128>(),
+            elapsed in 1..=u32::MAX as u64,
+        ) {
+            let price = price_q64(reserve_base, reserve_quote).unwrap();
+            let end = accumulate(start, price, elapsed);
+            // a price held over the window fits whenever price * elapsed does , even if the sum wraps
+            prop_assume!(price.checked_mul(elapsed as u128).is_some());
+            prop_assert_eq!(average_price(start, 0, end, elapsed as i64).unwrap(), price);
+        }
+
+        #[test]
+        fn averages_sit_between_the_prices_seen(
+            first in 0..=1u128 << 80,
+            second in 0..=1u128 << 80,
+            first_elapsed in 1..=1u64 << 20,
+            second_elapsed in 1..=1u64 << 20,
+            start in any::<u128>(),
+        ) {
+            let middle = accumulate(start, first, first_elapsed);
+            let end = accumulate(middle, second, second_elapsed);
+            let twap = average_price(start, 0, end, (first_elapsed + second_elapsed) as i64).unwrap();
+            prop_assert!(twap >= first.min(second) && twap <= first.max(second));
+        }
+    }
+
+    #[test]
+    fn price_is_quote_per_base() {
+        assert_eq!(price_q64(1_000, 4_000), Ok(4 * Q64));
+        assert_eq!(price_q64(4_000, 1_000), Ok(Q64 / 4));
+        assert_eq!(price_q64(0, 1), Err(CurveError::ZeroLiquidity));
+        assert_eq!(average_price(0, 5, 0, 5), Err(CurveError::EmptyWindow));
+    }
+}
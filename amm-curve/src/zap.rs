@@ -0,0 +1,148 @@
+//! Single sided deposits. Part of the input is swapped against the pool first , so what is left over
+//! and what the swap paid out sit in the pool's new ratio , then both go in as an ordinary deposit.
+
+use crate::{lp_for_deposit, CurveError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZapQuote {
+    pub swap_amount : u64,  // part of the input sold first , fee included
+    pub amount_out : u64,   // what that sale pays out , it never leaves the pool
+    pub lp : u64,
+}
+
+// largest part of `amount` to sell so the remainder still covers its share against the output
+// `quote` prices a sale of that size against the pool as it stands , so any curve works
+pub fn zap_swap_amount<F>(amount : u64 , reserve_in : u64 , reserve_out : u64 , quote : F) -> Result<u64, CurveError>
+where
+    F: Fn(u64) -> Result<u64, CurveError>,
+{
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(CurveError::ZeroLiquidity);
+    }
+
+    // after selling `sold` the user holds (amount - sold , out) against reserves (reserve_in + sold ,
+    // reserve_out - out) , the remainder shrinks and the output grows with `sold` so this flips once
+    let remainder_covers = |sold : u64| -> Result<bool, CurveError> {
+        let out = quote(sold)?;
+        let reserve_out_after = reserve_out.checked_sub(out).ok_or(CurveError::ZeroLiquidity)?;
+        Ok((amount - sold) as u128 * reserve_out_after as u128 >= out as u128 * (reserve_in as u128 + sold as u128))
+    };
+
+    let (mut low, mut high) = (0, amount);
+    while low < high {
+        let middle = low + (high - low).div_ceil(2);
+        if remainder_covers(middle)? {
+            low = middle;
+        } else {
+            high = middle - 1;
+        }
+    }
+    Ok(low)
+}
+
+// the whole single sided deposit , LP is priced against the reserves after the internal sale
+pub fn zap_deposit<F>(
+    amount : u64 ,
+    supply : u64 ,
+    reserve_in : u64 ,
+    reserve_out : u64 ,
+    quote : F
+) -> Result<ZapQuote, CurveError>
+where
+    F: Fn(u64) -> Result<u64, CurveError>,
+{
+    if supply == 0 {
+        return Err(CurveError::ZeroLiquidity);
+    }
+    let quote_at = |swap_amount : u64| -> Result<ZapQuote, CurveError> {
+        let amount_out = quote(swap_amount)?;
+        let reserve_in_after = reserve_in.checked_add(swap_amount).ok_or(CurveError::Overflow)?;
+        let lp = lp_for_deposit(amount - swap_amount, amount_out, supply, reserve_in_after, reserve_out - amount_out)?;
+        Ok(ZapQuote { swap_amount, amount_out, lp })
+    };
+
+    // up to `swap_amount` the output side caps the LP and keeps growing , past it the remainder caps it
+    // and keeps shrinking , so the best deposit is at the crossing or one unit beyond it
+    let swap_amount = zap_swap_amount(amount, reserve_in, reserve_out, &quote)?;
+    let best = quote_at(swap_amount)?;
+    if swap_amount == amount {
+        return Ok(best);
+    }
+    let next = quote_at(swap_amount + 1)?;
+    Ok(if next.lp > best.lp { next } else { best })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{stable_swap, swap_out, withdraw_amounts, Rounding};
+    use proptest::prelude::*;
+
+    fn reserve() -> impl Strategy<Value = u64> {
+        1_000..=1u64 << 48
+    }
+
+    fn product_quote(reserve_in : u64 , reserve_out : u64 , fee : u16) -> impl Fn(u64) -> Result<u64, CurveError> {
+        move |amount| swap_out(amount, reserve_in, reserve_out, fee).map(|quote| quote.amount_out)
+    }
+
+    proptest! {
+        #[test]
+        fn zapping_in_and_out_never_profits(
+            reserve_in in reserve(),
+            reserve_out in reserve(),
+            supply in 1..=1u64 << 48,
+            amount in 1..=1u64 << 40,
+            fee in 0..=1_000u16,
+        ) {
+            let zap = zap_deposit(amount, supply, reserve_in, reserve_out, product_quote(reserve_in, reserve_out, fee)).unwrap();
+            prop_assume!(zap.lp > 0);
+
+            // withdraw straight away and sell the out side back for the in side
+            let (reserve_in, reserve_out) = (reserve_in + amount, reserve_out);
+            let (back_in, back_out) = withdraw_amounts(zap.lp, supply + zap.lp, reserve_in, reserve_out, Rounding::Down).unwrap();
+            let sold = swap_out(back_out, reserve_out - back_out, reserve_in - back_in, fee).unwrap();
+            prop_assert!(back_in + sold.amount_out <= amount);
+        }
+
+        #[test]
+        fn no_other_swap_size_mints_more(
+            reserve_in in reserve(),
+            reserve_out in reserve(),
+            supply in 1..=1u64 << 48,
+            amount in 2..=1u64 << 40,
+            fee in 0..=1_000u16,
+        ) {
+            let quote = product_quote(reserve_in, reserve_out, fee);
+            let zap = zap_deposit(amount, supply, reserve_in, reserve_out, &quote).unwrap();
+            let lp_for = |sold : u64| {
+                let out = quote(sold).unwrap();
+                lp_for_deposit(amount - sold, out, supply, reserve_in + sold, reserve_out - out).unwrap()
+            };
+            for sold in [zap.swap_amount.saturating_sub(1), (zap.swap_amount + 1).min(amount), amount / 2] {
+                prop_assert!(lp_for(sold) <= zap.lp);
+            }
+        }
+
+        #[test]
+        fn stable_pools_zap_about_half_at_the_peg(
+            amp in 1..=10_000u64,
+            reserve in reserve(),
+            amount in 1_000..=1u64 << 30,
+        ) {
+            prop_assume!(amount <= reserve);
+            let quote = |sold| stable_swap::swap_out(sold, reserve, reserve, amp, 0).map(|quote| quote.amount_out);
+            let sold = zap_swap_amount(amount, reserve, reserve, quote).unwrap();
+            // a balanced pool with no fee wants roughly half of the input sold , less as the price moves
+            prop_assert!(sold <= amount / 2 + 1);
+            prop_assert!(sold * 3 >= amount);
+        }
+    }
+
+    #[test]
+    fn empty_pools_are_errors() {
+        let quote = |_| Ok(0);
+        assert_eq!(zap_swap_amount(10, 0, 10, quote), Err(CurveError::ZeroLiquidity));
+        assert_eq!(zap_deposit(10, 0, 10, 10, quote), Err(CurveError::ZeroLiquidity));
+    }
+}
@@ -0,0 +1,71 @@
+use amm_curve::zap::ZapQuote;
+use amm_curve::{CurveError, Rounding};
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+use crate::state::{Config, CurveType};
+
+// thin wrappers over the shared `amm_curve` crate that pick the rounding in the pool's favour
+// and turn its errors into program errors
+
+pub fn curve_error(err : CurveError) -> Error {
+    match err {
+        CurveError::ZeroLiquidity => error!(ErrorCode::NoLiquidity),
+        CurveError::Overflow => error!(ErrorCode::MathOverflow),
+        CurveError::InvalidFee => error!(ErrorCode::InvalidFee),
+        CurveError::InvalidAmp => error!(ErrorCode::InvalidAmp),
+        CurveError::NoConvergence => error!(ErrorCode::NoConvergence),
+        CurveError::EmptyWindow => error!(ErrorCode::NotEnoughObservations),
+    }
+}
+
+pub fn deposit_amounts(lp : u64 , supply : u64 , reserve_x : u64 , reserve_y : u64) -> Result<(u64, u64)> {
+    amm_curve::deposit_amounts(lp, supply, reserve_x, reserve_y, Rounding::Up).map_err(curve_error)
+}
+
+pub fn initial_lp(x : u64 , y : u64) -> Result<u64> {
+    amm_curve::initial_lp(x, y).map_err(curve_error)
+}
+
+pub fn withdraw_amounts(lp : u64 , supply : u64 , reserve_x : u64 , reserve_y : u64) -> Result<(u64, u64)> {
+    amm_curve::withdraw_amounts(lp, supply, reserve_x, reserve_y, Rounding::Down).map_err(curve_error)
+}
+
+// flash loans pay the pool's swap fee on the amount borrowed , rounded up
+pub fn flash_fee(amount : u64 , fee_bps : u16) -> Result<u64> {
+    amm_curve::mul_div(amount, fee_bps as u64, amm_curve::BPS_DENOMINATOR as u64, Rounding::Up).map_err(curve_error)
+}
+
+// deposits and withdrawals stay proportional on both curves , only swaps follow the pool's invariant
+fn quote(config : &Config , amount_in : u64 , reserve_in : u64 , reserve_out : u64 , now : i64) -> core::result::Result<u64, CurveError> {
+    let quote = match config.curve {
+        CurveType::ConstantProduct => amm_curve::swap_out(amount_in, reserve_in, reserve_out, config.fee),
+        CurveType::StableSwap => amm_curve::stable_swap::swap_out(
+            amount_in,
+            reserve_in,
+            reserve_out,
+            config.amp.amp(now),
+            config.fee,
+        ),
+    };
+    quote.map(|quote| quote.amount_out)
+}
+
+pub fn swap_out(config : &Config , amount_in : u64 , reserve_in : u64 , reserve_out : u64 , now : i64) -> Result<u64> {
+    quote(config, amount_in, reserve_in, reserve_out, now).map_err(curve_error)
+}
+
+// single sided deposit of `amount` , priced on the pool's own curve
+pub fn zap_deposit(
+    config : &Config ,
+    amount : u64 ,
+    supply : u64 ,
+    reserve_in : u64 ,
+    reserve_out : u64 ,
+    now : i64
+) -> Result<ZapQuote> {
+    amm_curve::zap::zap_deposit(amount, supply, reserve_in, reserve_out, |sold| {
+        quote(config, sold, reserve_in, reserve_out, now)
+    })
+    .map_err(curve_error)
+}
@@ -0,0 +1,53 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Fee can't exceed MAX_FEE_BPS")]
+    InvalidFee,
+    #[msg("Pool mints must differ")]
+    IdenticalMints,
+    #[msg("Pool is locked")]
+    PoolLocked,
+    #[msg("Pool has no liquidity")]
+    NoLiquidity,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Price moved beyond the given slippage bound")]
+    SlippageExceeded,
+    #[msg("Signer is not the pool authority")]
+    Unauthorized,
+    #[msg("Amplification is out of range for this pool type")]
+    InvalidAmp,
+    #[msg("Stable swap pools need mints with the same decimals")]
+    DecimalsMismatch,
+    #[msg("Pool is not a stable swap pool")]
+    NotStablePool,
+    #[msg("Amp ramp is too short or too steep")]
+    InvalidRamp,
+    #[msg("Curve solver did not converge")]
+    NoConvergence,
+    #[msg("Observations are too close together")]
+    ObservationTooSoon,
+    #[msg("No observation old enough for the window")]
+    NotEnoughObservations,
+    #[msg("Pool has a flash loan outstanding")]
+    FlashLoanActive,
+    #[msg("Flash borrow needs a flash repay for the same pool later in the transaction")]
+    MissingFlashRepay,
+    #[msg("Flash borrow can't be called through CPI")]
+    FlashBorrowViaCpi,
+    #[msg("No flash loan to repay")]
+    NoFlashLoan,
+    #[msg("Route is empty , too long or has the wrong number of accounts")]
+    InvalidRoute,
+    #[msg("Route account doesn't match its leg")]
+    RouteAccountMismatch,
+    #[msg("Mints don't match the pool")]
+    MintMismatch,
+    #[msg("Mints with a transfer fee can't be pooled")]
+    TransferFeeMint,
+    #[msg("First deposit must mint more LP than MINIMUM_LIQUIDITY")]
+    InitialLiquidityTooLow,
+}
@@ -0,0 +1,157 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{mint_to, transfer_checked, Mint, MintTo, TokenAccount, TokenInterface, TransferChecked}
+};
+use amm_curve::MINIMUM_LIQUIDITY;
+use crate::curve::{deposit_amounts, initial_lp};
+use crate::error::ErrorCode;
+use crate::state::Config;
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(mut)]
+    pub user : Signer<'info>,
+
+    pub mint_x : InterfaceAccount<'info , Mint>,
+    pub mint_y : InterfaceAccount<'info , Mint>,
+
+    #[account(
+        mut,
+        has_one = mint_x,
+        has_one = mint_y,
+        seeds= [b"config",config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config : Account<'info,Config>,
+
+    #[account(
+        mut,
+        seeds= [b"lp",config.key().as_ref()],
+        bump = config.lp_bump,
+    )]
+    pub mint_lp : InterfaceAccount<'info , Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = config,
+        associated_token::token_program = token_program
+    )]
+    pub vault_x : InterfaceAccount<'info , TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+        associated_token::token_program = token_program
+    )]
+    pub vault_y : InterfaceAccount<'info , TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = user,
+        associated_token::token_program = token_program
+    )]
+    pub user_x : InterfaceAccount<'info , TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = user,
+        associated_token::token_program = token_program
+    )]
+    pub user_y : InterfaceAccount<'info , TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_lp,
+        associated_token::authority = user,
+        associated_token::token_program = token_program
+    )]
+    pub user_lp : InterfaceAccount<'info , TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = config,
+        associated_token::token_program = token_program
+    )]
+    pub locked_lp : InterfaceAccount<'info , TokenAccount>,
+
+    pub associated_token_program : Program<'info,AssociatedToken>,
+    pub system_program : Program<'info , System>,
+    pub token_program : Interface<'info , TokenInterface>
+}
+
+impl<'info> Deposit<'info> {
+    pub fn deposit(&mut self , amount : u64 , max_x : u64 , max_y : u64) -> Result<()> {
+        require!(!self.config.locked, ErrorCode::PoolLocked);
+        self.config.require_no_flash_loan()?;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let now = Clock::get()?.unix_timestamp;
+        self.config.update_prices(self.vault_x.amount, self.vault_y.amount, now);
+
+        if self.mint_lp.supply == 0 {
+            return self.first_deposit(amount, max_x, max_y);
+        }
+
+        let (x, y) = deposit_amounts(amount, self.mint_lp.supply, self.vault_x.amount, self.vault_y.amount)?;
+        require!(x <= max_x && y <= max_y, ErrorCode::SlippageExceeded);
+
+        self.deposit_token(true, x)?;
+        self.deposit_token(false, y)?;
+        self.mint_lp_tokens(self.user_lp.to_account_info(), amount)
+    }
+
+    // puts in exactly `x` and `y` , mints their geometric mean as LP and locks MINIMUM_LIQUIDITY of it ,
+    // the user gets the rest which has to be at least `min_lp`
+    fn first_deposit(&mut self , min_lp : u64 , x : u64 , y : u64) -> Result<()> {
+        require!(x > 0 && y > 0, ErrorCode::InvalidAmount);
+        let lp = initial_lp(x, y)?;
+        require!(lp > MINIMUM_LIQUIDITY, ErrorCode::InitialLiquidityTooLow);
+        require!(lp - MINIMUM_LIQUIDITY >= min_lp, ErrorCode::SlippageExceeded);
+
+        self.deposit_token(true, x)?;
+        self.deposit_token(false, y)?;
+        self.mint_lp_tokens(self.locked_lp.to_account_info(), MINIMUM_LIQUIDITY)?;
+        self.mint_lp_tokens(self.user_lp.to_account_info(), lp - MINIMUM_LIQUIDITY)
+    }
+
+    fn deposit_token(&self , is_x : bool , amount : u64) -> Result<()> {
+        let (from, to, mint) = if is_x {
+            (&self.user_x, &self.vault_x, &self.mint_x)
+        } else {
+            (&self.user_y, &self.vault_y, &self.mint_y)
+        };
+
+        let transfer_accounts = TransferChecked{
+            from : from.to_account_info(),
+            mint : mint.to_account_info(),
+            to : to.to_account_info(),
+            authority : self.user.to_account_info()
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts);
+        transfer_checked(cpi_ctx, amount, mint.decimals)
+    }
+
+    fn mint_lp_tokens(&self , to : AccountInfo<'info> , amount : u64) -> Result<()> {
+        let signer_seeds :[&[&[u8]];1]=
+        [&[
+            b"config",
+            &self.config.seed.to_le_bytes()[..],
+            &[self.config.config_bump]
+        ]];
+
+        let mint_accounts = MintTo{
+            mint : self.mint_lp.to_account_info(),
+            to,
+            authority : self.config.to_account_info()
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), mint_accounts, &signer_seeds);
+        mint_to(cpi_ctx, amount)
+    }
+}
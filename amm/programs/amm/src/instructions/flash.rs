@@ -0,0 +1,160 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions::{
+    get_instruction_relative,
+    load_current_index_checked,
+    load_instruction_at_checked
+};
+use anchor_lang::Discriminator;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked}
+};
+use crate::curve::flash_fee;
+use crate::error::ErrorCode;
+use crate::state::{Config, FlashLoan};
+
+// where `config` sits in this struct , a repay is matched to its borrow by the key at this index
+const CONFIG_ACCOUNT_INDEX: usize = 3;
+
+// shared by `flash_borrow` and `flash_repay` , so both carry the pool at the same position
+#[derive(Accounts)]
+pub struct Flash<'info> {
+    #[account(mut)]
+    pub user : Signer<'info>,
+
+    pub mint_x : InterfaceAccount<'info , Mint>,
+    pub mint_y : InterfaceAccount<'info , Mint>,
+
+    #[account(
+        mut,
+        has_one = mint_x,
+        has_one = mint_y,
+        seeds= [b"config",config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config : Account<'info,Config>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = config,
+        associated_token::token_program = token_program
+    )]
+    pub vault_x : InterfaceAccount<'info , TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+        associated_token::token_program = token_program
+    )]
+    pub vault_y : InterfaceAccount<'info , TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_x,
+        associated_token::authority = user,
+        associated_token::token_program = token_program
+    )]
+    pub user_x : InterfaceAccount<'info , TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_y,
+        associated_token::authority = user,
+        associated_token::token_program = token_program
+    )]
+    pub user_y : InterfaceAccount<'info , TokenAccount>,
+
+    /// CHECK: the instructions sysvar , read to find the repay
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions : UncheckedAccount<'info>,
+
+    pub associated_token_program : Program<'info,AssociatedToken>,
+    pub system_program : Program<'info , System>,
+    pub token_program : Interface<'info , TokenInterface>
+}
+
+impl<'info> Flash<'info> {
+    pub fn flash_borrow(&mut self , is_x : bool , amount : u64) -> Result<()> {
+        require!(!self.config.locked, ErrorCode::PoolLocked);
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        self.config.require_no_flash_loan()?;
+        self.check_repay_follows()?;
+
+        let fee = flash_fee(amount, self.config.fee)?;
+        self.config.flash_loan = Some(FlashLoan { is_x, amount, fee });
+        self.withdraw_token(is_x, amount)
+    }
+
+    pub fn flash_repay(&mut self) -> Result<()> {
+        let loan = self.config.flash_loan.take().ok_or(ErrorCode::NoFlashLoan)?;
+        let repay = loan.amount.checked_add(loan.fee).ok_or(ErrorCode::MathOverflow)?;
+        self.deposit_token(loan.is_x, repay)
+    }
+
+    // the borrow has to be a top level instruction of this program , followed somewhere later in
+    // the transaction by a top level `flash_repay` on the same pool
+    fn check_repay_follows(&self) -> Result<()> {
+        let instructions = self.instructions.to_account_info();
+
+        // a CPI would show up here as the caller's instruction
+        let current = get_instruction_relative(0, &instructions)?;
+        require_keys_eq!(current.program_id, crate::ID, ErrorCode::FlashBorrowViaCpi);
+
+        let mut index = load_current_index_checked(&instructions)? as usize + 1;
+        while let Ok(instruction) = load_instruction_at_checked(index, &instructions) {
+            if instruction.program_id == crate::ID
+                && instruction.data.starts_with(crate::instruction::FlashRepay::DISCRIMINATOR)
+                && instruction.accounts.get(CONFIG_ACCOUNT_INDEX).map(|meta| meta.pubkey) == Some(self.config.key())
+            {
+                return Ok(());
+            }
+            index += 1;
+        }
+        err!(ErrorCode::MissingFlashRepay)
+    }
+
+    fn deposit_token(&self , is_x : bool , amount : u64) -> Result<()> {
+        let (from, to, mint) = if is_x {
+            (&self.user_x, &self.vault_x, &self.mint_x)
+        } else {
+            (&self.user_y, &self.vault_y, &self.mint_y)
+        };
+
+        let transfer_accounts = TransferChecked{
+            from : from.to_account_info(),
+            mint : mint.to_account_info(),
+            to : to.to_account_info(),
+            authority : self.user.to_account_info()
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts);
+        transfer_checked(cpi_ctx, amount, mint.decimals)
+    }
+
+    fn withdraw_token(&self , is_x : bool , amount : u64) -> Result<()> {
+        let (from, to, mint) = if is_x {
+            (&self.vault_x, &self.user_x, &self.mint_x)
+        } else {
+            (&self.vault_y, &self.user_y, &self.mint_y)
+        };
+
+        let signer_seeds :[&[&[u8]];1]=
+        [&[
+            b"config",
+            &self.config.seed.to_le_bytes()[..],
+            &[self.config.config_bump]
+        ]];
+
+        let transfer_accounts = TransferChecked{
+            from : from.to_account_info(),
+            mint : mint.to_account_info(),
+            to : to.to_account_info(),
+            authority : self.config.to_account_info()
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), transfer_accounts, &signer_seeds);
+        transfer_checked(cpi_ctx, amount, mint.decimals)
+    }
+}
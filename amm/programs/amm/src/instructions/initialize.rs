@@ -0,0 +1,133 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_2022::spl_token_2022::extension::transfer_fee::TransferFeeConfig,
+    token_interface::{get_mint_extension_data, Mint, TokenAccount, TokenInterface}
+};
+use crate::error::ErrorCode;
+use amm_curve::stable_swap::{MAX_AMP, MIN_AMP};
+use crate::state::{AmpRamp, Config, CurveType, MAX_FEE_BPS};
+
+#[derive(Accounts)]
+#[instruction(seed : u64)]
+pub struct Initialize<'info> {
+    #[account(mut)]
+    pub initializer : Signer<'info>,
+
+    #[account(
+        mint::token_program = token_program
+    )]
+    pub mint_x : InterfaceAccount<'info , Mint>,
+
+    #[account(
+        mint::token_program = token_program
+    )]
+    pub mint_y : InterfaceAccount<'info , Mint>,
+
+    #[account(
+        init,
+        payer = initializer,
+        seeds= [b"config",seed.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + Config::INIT_SPACE
+    )]
+    pub config : Account<'info,Config>,
+
+    // LP shares of the pool , only the config can mint them
+    #[account(
+        init,
+        payer = initializer,
+        seeds= [b"lp",config.key().as_ref()],
+        bump,
+        mint::decimals = 6,
+        mint::authority = config,
+        mint::token_program = token_program
+    )]
+    pub mint_lp : InterfaceAccount<'info , Mint>,
+
+    #[account(
+        init,
+        payer = initializer,
+        associated_token::mint = mint_x,
+        associated_token::authority = config,
+        associated_token::token_program = token_program
+    )]
+    pub vault_x : InterfaceAccount<'info , TokenAccount>,
+
+    #[account(
+        init,
+        payer = initializer,
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+        associated_token::token_program = token_program
+    )]
+    pub vault_y : InterfaceAccount<'info , TokenAccount>,
+
+    // holds the MINIMUM_LIQUIDITY LP of the first deposit , the config never moves it
+    #[account(
+        init,
+        payer = initializer,
+        associated_token::mint = mint_lp,
+        associated_token::authority = config,
+        associated_token::token_program = token_program
+    )]
+    pub locked_lp : InterfaceAccount<'info , TokenAccount>,
+
+    pub associated_token_program : Program<'info,AssociatedToken>,
+    pub system_program : Program<'info , System>,
+    // both mints and the LP mint live under this one token program
+    pub token_program : Interface<'info , TokenInterface>
+}
+
+impl<'info> Initialize<'info> {
+    pub fn init(
+        &mut self ,
+        seed : u64 ,
+        fee : u16 ,
+        authority : Option<Pubkey> ,
+        curve : CurveType ,
+        amp : u64 ,
+        bumps : &InitializeBumps
+    ) -> Result<()> {
+        require!(fee <= MAX_FEE_BPS, ErrorCode::InvalidFee);
+        require_keys_neq!(self.mint_x.key(), self.mint_y.key(), ErrorCode::IdenticalMints);
+        // pool math counts every token sent as received , a transfer fee would drain the vaults one swap at a time
+        for mint in [&self.mint_x, &self.mint_y] {
+            require!(
+                get_mint_extension_data::<TransferFeeConfig>(&mint.to_account_info()).is_err(),
+                ErrorCode::TransferFeeMint
+            );
+        }
+
+        let amp = match curve {
+            CurveType::ConstantProduct => {
+                require!(amp == 0, ErrorCode::InvalidAmp);
+                AmpRamp::fixed(0)
+            }
+            CurveType::StableSwap => {
+                require!((MIN_AMP..=MAX_AMP).contains(&amp), ErrorCode::InvalidAmp);
+                // the invariant compares raw amounts , so one unit of x has to be worth one unit of y
+                require!(self.mint_x.decimals == self.mint_y.decimals, ErrorCode::DecimalsMismatch);
+                AmpRamp::fixed(amp)
+            }
+        };
+
+        self.config.set_inner(Config {
+            seed,
+            authority,
+            mint_x: self.mint_x.key(),
+            mint_y: self.mint_y.key(),
+            fee,
+            curve,
+            amp,
+            locked: false,
+            price_x_cumulative: 0,
+            price_y_cumulative: 0,
+            last_update_ts: Clock::get()?.unix_timestamp,
+            flash_loan: None,
+            config_bump: bumps.config,
+            lp_bump: bumps.mint_lp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+pub mod initialize;
+pub use initialize::*;
+
+pub mod deposit;
+pub use deposit::*;
+
+pub mod withdraw;
+pub use withdraw::*;
+
+pub mod swap;
+pub use swap::*;
+
+pub mod lock;
+pub use lock::*;
+
+pub mod ramp;
+pub use ramp::*;
+
+pub mod oracle;
+pub use oracle::*;
+
+pub mod flash;
+pub use flash::*;
+
+pub mod route;
+pub use route::*;
+
+pub mod zap;
+pub use zap::*;
@@ -0,0 +1,101 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{TokenAccount, TokenInterface};
+use crate::state::{Config, Observation, Observations, MAX_OBSERVATIONS};
+
+#[derive(Accounts)]
+pub struct InitObservations<'info> {
+    #[account(mut)]
+    pub payer : Signer<'info>,
+
+    #[account(
+        seeds= [b"config",config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config : Account<'info,Config>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds= [b"observations",config.key().as_ref()],
+        bump,
+        space = 8 + Observations::INIT_SPACE
+    )]
+    pub observations : Account<'info,Observations>,
+
+    pub system_program : Program<'info , System>
+}
+
+impl<'info> InitObservations<'info> {
+    pub fn init_observations(&mut self , bumps : &InitObservationsBumps) -> Result<()> {
+        self.observations.set_inner(Observations {
+            config: self.config.key(),
+            next: 0,
+            observations: Vec::with_capacity(MAX_OBSERVATIONS),
+            bump: bumps.observations,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct RecordObservation<'info> {
+    #[account(
+        mut,
+        seeds= [b"config",config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config : Account<'info,Config>,
+
+    #[account(
+        associated_token::mint = config.mint_x,
+        associated_token::authority = config,
+        associated_token::token_program = token_program
+    )]
+    pub vault_x : InterfaceAccount<'info , TokenAccount>,
+
+    #[account(
+        associated_token::mint = config.mint_y,
+        associated_token::authority = config,
+        associated_token::token_program = token_program
+    )]
+    pub vault_y : InterfaceAccount<'info , TokenAccount>,
+
+    #[account(
+        mut,
+        has_one = config,
+        seeds= [b"observations",config.key().as_ref()],
+        bump = observations.bump,
+    )]
+    pub observations : Account<'info,Observations>,
+
+    pub token_program : Interface<'info , TokenInterface>
+}
+
+impl<'info> RecordObservation<'info> {
+    pub fn record_observation(&mut self) -> Result<()> {
+        self.config.require_no_flash_loan()?;
+        let now = Clock::get()?.unix_timestamp;
+        self.config.update_prices(self.vault_x.amount, self.vault_y.amount, now);
+        self.observations.push(Observation {
+            timestamp: now,
+            price_x_cumulative: self.config.price_x_cumulative,
+            price_y_cumulative: self.config.price_y_cumulative,
+        })
+    }
+}
+
+#[derive(Accounts)]
+pub struct Observe<'info> {
+    #[account(
+        seeds= [b"config",config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config : Account<'info,Config>,
+
+    #[account(
+        has_one = config,
+        seeds= [b"observations",config.key().as_ref()],
+        bump = observations.bump,
+    )]
+    pub observations : Account<'info,Observations>,
+}
@@ -0,0 +1,48 @@
+use amm_curve::stable_swap::{MAX_AMP, MIN_AMP};
+use anchor_lang::prelude::*;
+use crate::error::ErrorCode;
+use crate::state::{AmpRamp, Config, CurveType, MAX_AMP_CHANGE, MIN_RAMP_DURATION};
+
+#[derive(Accounts)]
+pub struct RampAmp<'info> {
+    pub authority : Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = config.authority == Some(authority.key()) @ ErrorCode::Unauthorized,
+        constraint = config.curve == CurveType::StableSwap @ ErrorCode::NotStablePool,
+        seeds= [b"config",config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config : Account<'info,Config>,
+}
+
+impl<'info> RampAmp<'info> {
+    pub fn ramp_amp(&mut self , target_amp : u64 , stop_ts : i64) -> Result<()> {
+        require!((MIN_AMP..=MAX_AMP).contains(&target_amp), ErrorCode::InvalidAmp);
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(stop_ts >= now.saturating_add(MIN_RAMP_DURATION), ErrorCode::InvalidRamp);
+
+        // a new ramp starts from wherever the current one has got to
+        let current = self.config.amp.amp(now);
+        require!(
+            target_amp <= current.saturating_mul(MAX_AMP_CHANGE) && current <= target_amp.saturating_mul(MAX_AMP_CHANGE),
+            ErrorCode::InvalidRamp
+        );
+
+        self.config.amp = AmpRamp {
+            initial_amp: current,
+            target_amp,
+            start_ts: now,
+            stop_ts,
+        };
+        Ok(())
+    }
+
+    pub fn stop_ramp_amp(&mut self) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        self.config.amp = AmpRamp::fixed(self.config.amp.amp(now));
+        Ok(())
+    }
+}
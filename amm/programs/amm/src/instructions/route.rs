@@ -0,0 +1,224 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{get_associated_token_address_with_program_id, AssociatedToken},
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked}
+};
+use escrow_solana_turbin3::cpi::accounts::Take;
+use escrow_solana_turbin3::program::EscrowSolanaTurbin3;
+use escrow_solana_turbin3::state::Escrow;
+use crate::curve::swap_out;
+use crate::error::ErrorCode;
+use crate::state::Config;
+
+// more hops than this won't fit in a transaction's compute budget anyway
+pub const MAX_ROUTE_LEGS: usize = 4;
+// (config , vault x , vault y , mint x , mint y , user ATA x , user ATA y , token program)
+pub const POOL_LEG_ACCOUNTS: usize = 8;
+// (maker , mint a , mint b , user ATA b , user ATA a , maker ATA b , escrow , vault , fee config ,
+//  treasury ATA b , order book , token program a , token program b) , the escrow program id stands in
+//  for an unlisted escrow's order book and for the treasury ATA while the escrow program charges no fee
+pub const ESCROW_LEG_ACCOUNTS: usize = 13;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub enum RouteLeg {
+    // swap through a pool of this program , constant product or stable , `is_x` sells token x
+    Pool { is_x : bool },
+    // takes as much token a from a swap escrow as the previous leg's output buys at its current price ,
+    // at most what is left in it , token b the fill doesn't spend stays in the user's ATA
+    // only token for token escrows open to the user without an allowlist proof can be routed through
+    EscrowOrder,
+}
+
+impl RouteLeg {
+    fn account_count(&self) -> usize {
+        match self {
+            RouteLeg::Pool { .. } => POOL_LEG_ACCOUNTS,
+            RouteLeg::EscrowOrder => ESCROW_LEG_ACCOUNTS,
+        }
+    }
+}
+
+// the legs' accounts follow in `remaining_accounts` , in route order
+// every hop pays out into one of the user's ATAs , which the next hop then spends from
+// each leg names its own token program , so spl token and token 2022 pools can share a route
+#[derive(Accounts)]
+pub struct Route<'info> {
+    #[account(mut)]
+    pub user : Signer<'info>,
+
+    pub escrow_program : Program<'info , EscrowSolanaTurbin3>,
+    pub associated_token_program : Program<'info,AssociatedToken>,
+    pub system_program : Program<'info , System>,
+}
+
+impl<'info> Route<'info> {
+    pub fn route(
+        &self ,
+        remaining : &'info [AccountInfo<'info>] ,
+        amount_in : u64 ,
+        min_out : u64 ,
+        legs : &[RouteLeg]
+    ) -> Result<()> {
+        require!(amount_in > 0, ErrorCode::InvalidAmount);
+        require!(!legs.is_empty() && legs.len() <= MAX_ROUTE_LEGS, ErrorCode::InvalidRoute);
+        let needed: usize = legs.iter().map(RouteLeg::account_count).sum();
+        require!(remaining.len() == needed, ErrorCode::InvalidRoute);
+
+        let now = Clock::get()?.unix_timestamp;
+        let mut amount = amount_in;
+        let mut previous_out = None;
+        let mut offset = 0;
+        for leg in legs {
+            let accounts = &remaining[offset..offset + leg.account_count()];
+            offset += leg.account_count();
+
+            let (amount_out, user_out) = match *leg {
+                RouteLeg::Pool { is_x } => self.pool_leg(accounts, is_x, amount, previous_out, now)?,
+                RouteLeg::EscrowOrder => self.escrow_leg(accounts, amount, previous_out, now)?,
+            };
+            amount = amount_out;
+            previous_out = Some(user_out);
+        }
+
+        // one bound for the whole route , intermediate hops take whatever price they get
+        require!(amount >= min_out, ErrorCode::SlippageExceeded);
+        Ok(())
+    }
+
+    // returns the amount paid out and the user ATA it went to
+    fn pool_leg(
+        &self ,
+        accounts : &'info [AccountInfo<'info>] ,
+        is_x : bool ,
+        amount_in : u64 ,
+        previous_out : Option<Pubkey> ,
+        now : i64
+    ) -> Result<(u64, Pubkey)> {
+        let [config, vault_x, vault_y, mint_x, mint_y, user_x, user_y, token_program] = accounts else {
+            return err!(ErrorCode::InvalidRoute);
+        };
+        let token_program = Interface::<TokenInterface>::try_from(token_program)?;
+        // the pool's vaults were created under the program owning its mints
+        require_keys_eq!(*mint_x.owner, token_program.key(), ErrorCode::RouteAccountMismatch);
+
+        // owner and discriminator checks , only `initialize` can have created it
+        let mut config = Account::<Config>::try_from(config)?;
+        require!(!config.locked, ErrorCode::PoolLocked);
+        config.require_no_flash_loan()?;
+
+        let mint_x = InterfaceAccount::<Mint>::try_from(mint_x)?;
+        let mint_y = InterfaceAccount::<Mint>::try_from(mint_y)?;
+        require_keys_eq!(mint_x.key(), config.mint_x, ErrorCode::RouteAccountMismatch);
+        require_keys_eq!(mint_y.key(), config.mint_y, ErrorCode::RouteAccountMismatch);
+
+        let vault_x = associated_account(vault_x, &config.key(), &config.mint_x, &token_program)?;
+        let vault_y = associated_account(vault_y, &config.key(), &config.mint_y, &token_program)?;
+        let user_x = associated_account(user_x, self.user.key, &config.mint_x, &token_program)?;
+        let user_y = associated_account(user_y, self.user.key, &config.mint_y, &token_program)?;
+
+        let (user_in, vault_in, mint_in, vault_out, user_out, mint_out) = if is_x {
+            (&user_x, &vault_x, &mint_x, &vault_y, &user_y, &mint_y)
+        } else {
+            (&user_y, &vault_y, &mint_y, &vault_x, &user_x, &mint_x)
+        };
+        if let Some(previous_out) = previous_out {
+            require_keys_eq!(user_in.key(), previous_out, ErrorCode::RouteAccountMismatch);
+        }
+
+        let amount_out = swap_out(&config, amount_in, vault_in.amount, vault_out.amount, now)?;
+        require!(amount_out > 0, ErrorCode::SlippageExceeded);
+        config.update_prices(vault_x.amount, vault_y.amount, now);
+
+        transfer(&token_program, user_in, mint_in, vault_in, self.user.to_account_info(), &[], amount_in)?;
+
+        let seed = config.seed.to_le_bytes();
+        let bump = [config.config_bump];
+        let signer_seeds :[&[&[u8]];1]= [&[b"config", &seed[..], &bump]];
+        transfer(&token_program, vault_out, mint_out, user_out, config.to_account_info(), &signer_seeds, amount_out)?;
+
+        config.exit(&crate::ID)?;
+        Ok((amount_out, user_out.key()))
+    }
+
+    // fills through the escrow program's own `take` , which checks every account of the fill and that
+    // `budget` covers the price
+    fn escrow_leg(
+        &self ,
+        accounts : &'info [AccountInfo<'info>] ,
+        budget : u64 ,
+        previous_out : Option<Pubkey> ,
+        now : i64
+    ) -> Result<(u64, Pubkey)> {
+        let [maker, mint_a, mint_b, user_ata_b, user_ata_a, maker_ata_b, escrow, vault, fee_config, treasury_ata_b, order_book, token_program_a, token_program_b] = accounts else {
+            return err!(ErrorCode::InvalidRoute);
+        };
+        if let Some(previous_out) = previous_out {
+            require_keys_eq!(user_ata_b.key(), previous_out, ErrorCode::RouteAccountMismatch);
+        }
+
+        // the most token a `budget` buys at the price `take` quotes in this same slot , rounding the fill
+        // down keeps `Escrow::quote` , which rounds up , within the budget
+        // a mint b with a transfer fee is grossed up on top of the quote and can fail the leg
+        let order = Account::<Escrow>::try_from(escrow)?;
+        let affordable = (budget as u128 * order.deposit_amount as u128)
+            .checked_div(order.current_price(now) as u128)
+            .ok_or(ErrorCode::SlippageExceeded)?;
+        let amount = affordable.min(order.remaining_amount as u128) as u64;
+        require!(amount > 0, ErrorCode::SlippageExceeded);
+
+        let optional = |account : &AccountInfo<'info>| (account.key() != self.escrow_program.key()).then(|| account.clone());
+        let take_accounts = Take {
+            taker : self.user.to_account_info(),
+            maker : maker.clone(),
+            mint_a : mint_a.clone(),
+            mint_b : mint_b.clone(),
+            taker_ata_b : user_ata_b.clone(),
+            taker_ata_a : user_ata_a.clone(),
+            maker_ata_b : maker_ata_b.clone(),
+            escrow : escrow.clone(),
+            vault : vault.clone(),
+            config : fee_config.clone(),
+            treasury_ata_b : optional(treasury_ata_b),
+            referrer_ata_b : None,
+            order_book : optional(order_book),
+            associated_token_program : self.associated_token_program.to_account_info(),
+            system_program : self.system_program.to_account_info(),
+            token_program_a : token_program_a.clone(),
+            token_program_b : token_program_b.clone(),
+        };
+        let cpi_ctx = CpiContext::new(self.escrow_program.to_account_info(), take_accounts);
+        escrow_solana_turbin3::cpi::take(cpi_ctx, amount, budget, Vec::new())?;
+
+        Ok((amount, user_ata_a.key()))
+    }
+}
+
+fn associated_account<'info>(
+    account : &'info AccountInfo<'info> ,
+    owner : &Pubkey ,
+    mint : &Pubkey ,
+    token_program : &Interface<'info , TokenInterface>
+) -> Result<InterfaceAccount<'info, TokenAccount>> {
+    let expected = get_associated_token_address_with_program_id(owner, mint, token_program.key);
+    require_keys_eq!(account.key(), expected, ErrorCode::RouteAccountMismatch);
+    InterfaceAccount::<TokenAccount>::try_from(account)
+}
+
+fn transfer<'info>(
+    token_program : &Interface<'info , TokenInterface> ,
+    from : &InterfaceAccount<'info, TokenAccount> ,
+    mint : &InterfaceAccount<'info, Mint> ,
+    to : &InterfaceAccount<'info, TokenAccount> ,
+    authority : AccountInfo<'info> ,
+    signer_seeds : &[&[&[u8]]] ,
+    amount : u64
+) -> Result<()> {
+    let transfer_accounts = TransferChecked{
+        from : from.to_account_info(),
+        mint : mint.to_account_info(),
+        to : to.to_account_info(),
+        authority
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), transfer_accounts, signer_seeds);
+    transfer_checked(cpi_ctx, amount, mint.decimals)
+}
@@ -0,0 +1,126 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked}
+};
+use crate::curve::swap_out;
+use crate::error::ErrorCode;
+use crate::state::Config;
+
+#[derive(Accounts)]
+pub struct Swap<'info> {
+    #[account(mut)]
+    pub user : Signer<'info>,
+
+    pub mint_x : InterfaceAccount<'info , Mint>,
+    pub mint_y : InterfaceAccount<'info , Mint>,
+
+    #[account(
+        mut,
+        has_one = mint_x,
+        has_one = mint_y,
+        seeds= [b"config",config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config : Account<'info,Config>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = config,
+        associated_token::token_program = token_program
+    )]
+    pub vault_x : InterfaceAccount<'info , TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+        associated_token::token_program = token_program
+    )]
+    pub vault_y : InterfaceAccount<'info , TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_x,
+        associated_token::authority = user,
+        associated_token::token_program = token_program
+    )]
+    pub user_x : InterfaceAccount<'info , TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_y,
+        associated_token::authority = user,
+        associated_token::token_program = token_program
+    )]
+    pub user_y : InterfaceAccount<'info , TokenAccount>,
+
+    pub associated_token_program : Program<'info,AssociatedToken>,
+    pub system_program : Program<'info , System>,
+    pub token_program : Interface<'info , TokenInterface>
+}
+
+impl<'info> Swap<'info> {
+    pub fn swap(&mut self , is_x : bool , amount_in : u64 , min_out : u64) -> Result<()> {
+        require!(!self.config.locked, ErrorCode::PoolLocked);
+        self.config.require_no_flash_loan()?;
+        require!(amount_in > 0, ErrorCode::InvalidAmount);
+
+        let (reserve_in, reserve_out) = if is_x {
+            (self.vault_x.amount, self.vault_y.amount)
+        } else {
+            (self.vault_y.amount, self.vault_x.amount)
+        };
+        let now = Clock::get()?.unix_timestamp;
+        let amount_out = swap_out(&self.config, amount_in, reserve_in, reserve_out, now)?;
+        self.config.update_prices(self.vault_x.amount, self.vault_y.amount, now);
+        require!(amount_out > 0 && amount_out >= min_out, ErrorCode::SlippageExceeded);
+
+        self.deposit_token(is_x, amount_in)?;
+        self.withdraw_token(!is_x, amount_out)
+    }
+
+    fn deposit_token(&self , is_x : bool , amount : u64) -> Result<()> {
+        let (from, to, mint) = if is_x {
+            (&self.user_x, &self.vault_x, &self.mint_x)
+        } else {
+            (&self.user_y, &self.vault_y, &self.mint_y)
+        };
+
+        let transfer_accounts = TransferChecked{
+            from : from.to_account_info(),
+            mint : mint.to_account_info(),
+            to : to.to_account_info(),
+            authority : self.user.to_account_info()
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts);
+        transfer_checked(cpi_ctx, amount, mint.decimals)
+    }
+
+    fn withdraw_token(&self , is_x : bool , amount : u64) -> Result<()> {
+        let (from, to, mint) = if is_x {
+            (&self.vault_x, &self.user_x, &self.mint_x)
+        } else {
+            (&self.vault_y, &self.user_y, &self.mint_y)
+        };
+
+        let signer_seeds :[&[&[u8]];1]=
+        [&[
+            b"config",
+            &self.config.seed.to_le_bytes()[..],
+            &[self.config.config_bump]
+        ]];
+
+        let transfer_accounts = TransferChecked{
+            from : from.to_account_info(),
+            mint : mint.to_account_info(),
+            to : to.to_account_info(),
+            authority : self.config.to_account_info()
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), transfer_accounts, &signer_seeds);
+        transfer_checked(cpi_ctx, amount, mint.decimals)
+    }
+}
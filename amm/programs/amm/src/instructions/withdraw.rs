@@ -0,0 +1,131 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{burn, transfer_checked, Burn, Mint, TokenAccount, TokenInterface, TransferChecked}
+};
+use crate::curve::withdraw_amounts;
+use crate::error::ErrorCode;
+use crate::state::Config;
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut)]
+    pub user : Signer<'info>,
+
+    pub mint_x : InterfaceAccount<'info , Mint>,
+    pub mint_y : InterfaceAccount<'info , Mint>,
+
+    #[account(
+        mut,
+        has_one = mint_x,
+        has_one = mint_y,
+        seeds= [b"config",config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config : Account<'info,Config>,
+
+    #[account(
+        mut,
+        seeds= [b"lp",config.key().as_ref()],
+        bump = config.lp_bump,
+    )]
+    pub mint_lp : InterfaceAccount<'info , Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = config,
+        associated_token::token_program = token_program
+    )]
+    pub vault_x : InterfaceAccount<'info , TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+        associated_token::token_program = token_program
+    )]
+    pub vault_y : InterfaceAccount<'info , TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_x,
+        associated_token::authority = user,
+        associated_token::token_program = token_program
+    )]
+    pub user_x : InterfaceAccount<'info , TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_y,
+        associated_token::authority = user,
+        associated_token::token_program = token_program
+    )]
+    pub user_y : InterfaceAccount<'info , TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = user,
+        associated_token::token_program = token_program
+    )]
+    pub user_lp : InterfaceAccount<'info , TokenAccount>,
+
+    pub associated_token_program : Program<'info,AssociatedToken>,
+    pub system_program : Program<'info , System>,
+    pub token_program : Interface<'info , TokenInterface>
+}
+
+impl<'info> Withdraw<'info> {
+    // a locked pool still lets LPs leave
+    pub fn withdraw(&mut self , amount : u64 , min_x : u64 , min_y : u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        self.config.require_no_flash_loan()?;
+
+        let (x, y) = withdraw_amounts(amount, self.mint_lp.supply, self.vault_x.amount, self.vault_y.amount)?;
+        require!(x >= min_x && y >= min_y, ErrorCode::SlippageExceeded);
+
+        let now = Clock::get()?.unix_timestamp;
+        self.config.update_prices(self.vault_x.amount, self.vault_y.amount, now);
+
+        self.burn_lp_tokens(amount)?;
+        self.withdraw_token(true, x)?;
+        self.withdraw_token(false, y)
+    }
+
+    fn burn_lp_tokens(&self , amount : u64) -> Result<()> {
+        let burn_accounts = Burn{
+            mint : self.mint_lp.to_account_info(),
+            from : self.user_lp.to_account_info(),
+            authority : self.user.to_account_info()
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), burn_accounts);
+        burn(cpi_ctx, amount)
+    }
+
+    fn withdraw_token(&self , is_x : bool , amount : u64) -> Result<()> {
+        let (from, to, mint) = if is_x {
+            (&self.vault_x, &self.user_x, &self.mint_x)
+        } else {
+            (&self.vault_y, &self.user_y, &self.mint_y)
+        };
+
+        let signer_seeds :[&[&[u8]];1]=
+        [&[
+            b"config",
+            &self.config.seed.to_le_bytes()[..],
+            &[self.config.config_bump]
+        ]];
+
+        let transfer_accounts = TransferChecked{
+            from : from.to_account_info(),
+            mint : mint.to_account_info(),
+            to : to.to_account_info(),
+            authority : self.config.to_account_info()
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), transfer_accounts, &signer_seeds);
+        transfer_checked(cpi_ctx, amount, mint.decimals)
+    }
+}
@@ -0,0 +1,124 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{mint_to, transfer_checked, Mint, MintTo, TokenAccount, TokenInterface, TransferChecked}
+};
+use crate::curve::zap_deposit;
+use crate::error::ErrorCode;
+use crate::state::Config;
+
+// like `Deposit` , but the user only needs to hold the side they pay in
+#[derive(Accounts)]
+pub struct DepositSingle<'info> {
+    #[account(mut)]
+    pub user : Signer<'info>,
+
+    pub mint_in : InterfaceAccount<'info , Mint>,
+    pub mint_out : InterfaceAccount<'info , Mint>,
+
+    #[account(
+        mut,
+        constraint = (config.mint_x, config.mint_y) == (mint_in.key(), mint_out.key())
+            || (config.mint_y, config.mint_x) == (mint_in.key(), mint_out.key()) @ ErrorCode::MintMismatch,
+        seeds= [b"config",config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config : Account<'info,Config>,
+
+    #[account(
+        mut,
+        seeds= [b"lp",config.key().as_ref()],
+        bump = config.lp_bump,
+    )]
+    pub mint_lp : InterfaceAccount<'info , Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_in,
+        associated_token::authority = config,
+        associated_token::token_program = token_program
+    )]
+    pub vault_in : InterfaceAccount<'info , TokenAccount>,
+
+    // only read , the internal swap's output never leaves the pool
+    #[account(
+        associated_token::mint = mint_out,
+        associated_token::authority = config,
+        associated_token::token_program = token_program
+    )]
+    pub vault_out : InterfaceAccount<'info , TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_in,
+        associated_token::authority = user,
+        associated_token::token_program = token_program
+    )]
+    pub user_in : InterfaceAccount<'info , TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_lp,
+        associated_token::authority = user,
+        associated_token::token_program = token_program
+    )]
+    pub user_lp : InterfaceAccount<'info , TokenAccount>,
+
+    pub associated_token_program : Program<'info,AssociatedToken>,
+    pub system_program : Program<'info , System>,
+    pub token_program : Interface<'info , TokenInterface>
+}
+
+impl<'info> DepositSingle<'info> {
+    // the whole `amount` goes into the vault , the swapped part pays for the other side's share
+    // the first deposit has to go through `deposit` , an empty pool has no ratio to zap into
+    pub fn deposit_single(&mut self , amount : u64 , min_lp : u64) -> Result<()> {
+        require!(!self.config.locked, ErrorCode::PoolLocked);
+        self.config.require_no_flash_loan()?;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let now = Clock::get()?.unix_timestamp;
+        let (reserve_in, reserve_out) = (self.vault_in.amount, self.vault_out.amount);
+        let zap = zap_deposit(&self.config, amount, self.mint_lp.supply, reserve_in, reserve_out, now)?;
+        require!(zap.lp > 0 && zap.lp >= min_lp, ErrorCode::SlippageExceeded);
+
+        let (reserve_x, reserve_y) = if self.mint_in.key() == self.config.mint_x {
+            (reserve_in, reserve_out)
+        } else {
+            (reserve_out, reserve_in)
+        };
+        self.config.update_prices(reserve_x, reserve_y, now);
+
+        self.deposit_token(amount)?;
+        self.mint_lp_tokens(zap.lp)
+    }
+
+    fn deposit_token(&self , amount : u64) -> Result<()> {
+        let transfer_accounts = TransferChecked{
+            from : self.user_in.to_account_info(),
+            mint : self.mint_in.to_account_info(),
+            to : self.vault_in.to_account_info(),
+            authority : self.user.to_account_info()
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts);
+        transfer_checked(cpi_ctx, amount, self.mint_in.decimals)
+    }
+
+    fn mint_lp_tokens(&self , amount : u64) -> Result<()> {
+        let signer_seeds :[&[&[u8]];1]=
+        [&[
+            b"config",
+            &self.config.seed.to_le_bytes()[..],
+            &[self.config.config_bump]
+        ]];
+
+        let mint_accounts = MintTo{
+            mint : self.mint_lp.to_account_info(),
+            to : self.user_lp.to_account_info(),
+            authority : self.config.to_account_info()
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), mint_accounts, &signer_seeds);
+        mint_to(cpi_ctx, amount)
+    }
+}
@@ -0,0 +1,104 @@
+use anchor_lang::prelude::*;
+
+pub mod curve;
+pub mod error;
+pub mod instructions;
+pub mod state;
+
+pub use instructions::*;
+pub use state::*;
+
+declare_id!("D327YnZA2wj2fQYUF1TFyJHLHvLhUQBTSm64MVaZSCKS");
+
+#[program]
+pub mod amm {
+    use super::*;
+
+    // `fee` is the swap fee in basis points , an `authority` of None makes the pool impossible to lock
+    // `amp` is the starting amplification of a stable swap pool and must be 0 for constant product
+    pub fn initialize(
+        ctx: Context<Initialize>,
+        seed : u64 ,
+        fee : u16 ,
+        authority : Option<Pubkey> ,
+        curve : CurveType ,
+        amp : u64
+    ) -> Result<()> {
+        ctx.accounts.init(seed, fee, authority, curve, amp, &ctx.bumps)
+    }
+
+    // mints `amount` LP tokens , `max_x` / `max_y` cap what the user pays
+    // the first deposit into an empty pool puts in exactly `max_x` and `max_y` and sets the price ,
+    // it mints sqrt(x * y) LP , locks MINIMUM_LIQUIDITY of it and `amount` is the least the user accepts
+    pub fn deposit(ctx: Context<Deposit>, amount : u64 , max_x : u64 , max_y : u64) -> Result<()> {
+        ctx.accounts.deposit(amount, max_x, max_y)
+    }
+
+    // deposits `amount` of `mint_in` alone , part of it is swapped against the pool first so the rest
+    // matches the pool ratio , fails unless at least `min_lp` LP tokens are minted
+    pub fn deposit_single(ctx: Context<DepositSingle>, amount : u64 , min_lp : u64) -> Result<()> {
+        ctx.accounts.deposit_single(amount, min_lp)
+    }
+
+    // burns `amount` LP tokens , `min_x` / `min_y` are the least the user accepts back
+    pub fn withdraw(ctx: Context<Withdraw>, amount : u64 , min_x : u64 , min_y : u64) -> Result<()> {
+        ctx.accounts.withdraw(amount, min_x, min_y)
+    }
+
+    // `is_x` sells token x for token y , otherwise token y for token x
+    pub fn swap(ctx: Context<Swap>, is_x : bool , amount_in : u64 , min_out : u64) -> Result<()> {
+        ctx.accounts.swap(is_x, amount_in, min_out)
+    }
+
+    // a locked pool rejects swaps and deposits , withdrawals stay open
+    pub fn set_locked(ctx: Context<SetLocked>, locked : bool) -> Result<()> {
+        ctx.accounts.set_locked(locked)
+    }
+
+    // moves a stable swap pool's amp linearly from its current value to `target_amp` at `stop_ts`
+    pub fn ramp_amp(ctx: Context<RampAmp>, target_amp : u64 , stop_ts : i64) -> Result<()> {
+        ctx.accounts.ramp_amp(target_amp, stop_ts)
+    }
+
+    // freezes the amp wherever an unfinished ramp has got to
+    pub fn stop_ramp_amp(ctx: Context<RampAmp>) -> Result<()> {
+        ctx.accounts.stop_ramp_amp()
+    }
+
+    // runs `legs` in order , each one selling what the previous one bought , and checks only the
+    // final output against `min_out` , see `RouteLeg` for the accounts each leg takes
+    pub fn route<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Route<'info>>,
+        amount_in : u64 ,
+        min_out : u64 ,
+        legs : Vec<RouteLeg>
+    ) -> Result<()> {
+        ctx.accounts.route(ctx.remaining_accounts, amount_in, min_out, &legs)
+    }
+
+    // lends `amount` of token x (or y) out of the vault until the `flash_repay` that must follow
+    // in the same transaction , swaps and liquidity changes on the pool are blocked in between
+    pub fn flash_borrow(ctx: Context<Flash>, is_x : bool , amount : u64) -> Result<()> {
+        ctx.accounts.flash_borrow(is_x, amount)
+    }
+
+    // pays back the outstanding loan plus the pool fee on it , which stays with the LPs
+    pub fn flash_repay(ctx: Context<Flash>) -> Result<()> {
+        ctx.accounts.flash_repay()
+    }
+
+    // opens the observation ring buffer of a pool , anyone can pay for it
+    pub fn init_observations(ctx: Context<InitObservations>) -> Result<()> {
+        ctx.accounts.init_observations(&ctx.bumps)
+    }
+
+    // permissionless crank , brings the price accumulators up to now and stores them in the ring buffer
+    pub fn record_observation(ctx: Context<RecordObservation>) -> Result<()> {
+        ctx.accounts.record_observation()
+    }
+
+    // read-only , the Q64.64 average prices over at least the last `window` seconds of observations
+    pub fn observe(ctx: Context<Observe>, window : i64) -> Result<Twap> {
+        ctx.accounts.observations.observe(window)
+    }
+}
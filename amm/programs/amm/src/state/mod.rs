@@ -0,0 +1,5 @@
+pub mod pool;
+pub use pool::*;
+
+pub mod observations;
+pub use observations::*;
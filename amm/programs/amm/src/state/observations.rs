@@ -0,0 +1,81 @@
+use amm_curve::twap::average_price;
+use anchor_lang::prelude::*;
+use crate::curve::curve_error;
+use crate::error::ErrorCode;
+
+// size of the ring buffer , with MIN_OBSERVATION_INTERVAL apart it always covers at least 32 minutes
+pub const MAX_OBSERVATIONS: usize = 32;
+pub const MIN_OBSERVATION_INTERVAL: i64 = 60;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct Observation {
+    pub timestamp : i64,
+    pub price_x_cumulative : u128,
+    pub price_y_cumulative : u128,
+}
+
+// what `observe` hands back , both prices are Q64.64
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct Twap {
+    pub price_x : u128,   // token x priced in token y
+    pub price_y : u128,   // token y priced in token x
+    pub start_ts : i64,
+    pub end_ts : i64,
+}
+
+// one per pool , snapshots of the config's price accumulators
+#[account]
+#[derive(InitSpace)]
+pub struct Observations {
+    pub config : Pubkey,
+    pub next : u8,                  // slot the next observation overwrites once the buffer is full
+    #[max_len(MAX_OBSERVATIONS)]
+    pub observations : Vec<Observation>,
+    pub bump : u8,
+}
+
+impl Observations {
+    pub fn latest(&self) -> Option<&Observation> {
+        if self.observations.is_empty() {
+            return None;
+        }
+        let last = (self.next as usize + self.observations.len() - 1) % self.observations.len();
+        self.observations.get(last)
+    }
+
+    pub fn push(&mut self , observation : Observation) -> Result<()> {
+        if let Some(latest) = self.latest() {
+            require!(
+                observation.timestamp >= latest.timestamp + MIN_OBSERVATION_INTERVAL,
+                ErrorCode::ObservationTooSoon
+            );
+        }
+        if self.observations.len() < MAX_OBSERVATIONS {
+            self.observations.push(observation);
+        } else {
+            self.observations[self.next as usize] = observation;
+        }
+        self.next = ((self.next as usize + 1) % MAX_OBSERVATIONS) as u8;
+        Ok(())
+    }
+
+    // averages from the newest observation back to the newest one at least `window` seconds older
+    pub fn observe(&self , window : i64) -> Result<Twap> {
+        require!(window > 0, ErrorCode::InvalidAmount);
+        let end = self.latest().ok_or(ErrorCode::NotEnoughObservations)?;
+        let start = self.observations
+            .iter()
+            .filter(|observation| observation.timestamp <= end.timestamp - window)
+            .max_by_key(|observation| observation.timestamp)
+            .ok_or(ErrorCode::NotEnoughObservations)?;
+
+        Ok(Twap {
+            price_x: average_price(start.price_x_cumulative, start.timestamp, end.price_x_cumulative, end.timestamp)
+                .map_err(curve_error)?,
+            price_y: average_price(start.price_y_cumulative, start.timestamp, end.price_y_cumulative, end.timestamp)
+                .map_err(curve_error)?,
+            start_ts: start.timestamp,
+            end_ts: end.timestamp,
+        })
+    }
+}
@@ -0,0 +1,102 @@
+use amm_curve::stable_swap::{compute_d, ramp_amp, spot_price_q64};
+use amm_curve::twap::{accumulate, price_q64};
+use amm_curve::CurveError;
+use anchor_lang::prelude::*;
+use crate::error::ErrorCode;
+
+// highest swap fee a pool can charge , 10%
+pub const MAX_FEE_BPS: u16 = 1_000;
+
+// an amplification ramp has to run at least a day , and can't move the amp more than 10x either way
+pub const MIN_RAMP_DURATION: i64 = 86_400;
+pub const MAX_AMP_CHANGE: u64 = 10;
+
+// picked at initialize and fixed for the life of the pool
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum CurveType {
+    ConstantProduct,
+    StableSwap,        // for pegged pairs , both mints need the same decimals
+}
+
+// an outstanding flash loan , set by `flash_borrow` and cleared by the matching `flash_repay`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct FlashLoan {
+    pub is_x : bool,
+    pub amount : u64,
+    pub fee : u64,
+}
+
+// stable swap amplification , moves linearly from `initial_amp` to `target_amp` over the ramp
+// a pool that isn't ramping has both ends equal
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct AmpRamp {
+    pub initial_amp : u64,
+    pub target_amp : u64,
+    pub start_ts : i64,
+    pub stop_ts : i64,
+}
+
+impl AmpRamp {
+    pub fn fixed(amp : u64) -> Self {
+        Self { initial_amp: amp, target_amp: amp, start_ts: 0, stop_ts: 0 }
+    }
+
+    pub fn amp(&self , now : i64) -> u64 {
+        ramp_amp(self.initial_amp, self.target_amp, self.start_ts, self.stop_ts, now)
+    }
+}
+
+// one per pool , owns both vaults and is the mint authority of the LP mint
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub seed : u64,
+    pub authority : Option<Pubkey>, // can lock and unlock the pool
+    pub mint_x : Pubkey,
+    pub mint_y : Pubkey,
+    pub fee : u16,                  // swap fee in basis points , stays in the pool for the LPs
+    pub curve : CurveType,
+    pub amp : AmpRamp,              // unused (all zero) on constant product pools
+    pub locked : bool,
+    // running sums of the Q64.64 spot price of x in y and of y in x on the pool's curve , times the seconds each was held
+    pub price_x_cumulative : u128,
+    pub price_y_cumulative : u128,
+    pub last_update_ts : i64,
+    pub flash_loan : Option<FlashLoan>,
+    pub config_bump : u8,
+    pub lp_bump : u8,
+}
+
+impl Config {
+    // the vaults are short while a flash loan is out , nothing may price off them until it's repaid
+    pub fn require_no_flash_loan(&self) -> Result<()> {
+        require!(self.flash_loan.is_none(), ErrorCode::FlashLoanActive);
+        Ok(())
+    }
+
+    // called with the reserves as they were *before* a swap or liquidity change , so the time since
+    // the last update is credited to the price that actually held over it
+    pub fn update_prices(&mut self , reserve_x : u64 , reserve_y : u64 , now : i64) {
+        let elapsed = now.saturating_sub(self.last_update_ts);
+        if elapsed > 0 {
+            if let Ok((price_x, price_y)) = self.spot_prices(reserve_x, reserve_y) {
+                self.price_x_cumulative = accumulate(self.price_x_cumulative, price_x, elapsed as u64);
+                self.price_y_cumulative = accumulate(self.price_y_cumulative, price_y, elapsed as u64);
+            }
+        }
+        self.last_update_ts = now;
+    }
+
+    // (price of x in y , price of y in x) as the curve quoted them since the last update ,
+    // a stable pool near its peg prices close to one whatever the reserve ratio is
+    fn spot_prices(&self , reserve_x : u64 , reserve_y : u64) -> core::result::Result<(u128, u128), CurveError> {
+        match self.curve {
+            CurveType::ConstantProduct => Ok((price_q64(reserve_x, reserve_y)?, price_q64(reserve_y, reserve_x)?)),
+            CurveType::StableSwap => {
+                let amp = self.amp.amp(self.last_update_ts);
+                let d = compute_d(amp, reserve_x, reserve_y)?;
+                Ok((spot_price_q64(amp, d, reserve_x, reserve_y)?, spot_price_q64(amp, d, reserve_y, reserve_x)?))
+            }
+        }
+    }
+}
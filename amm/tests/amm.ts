@@ -0,0 +1,682 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program, BN } from "@coral-xyz/anchor";
+import {
+  Keypair,
+  PublicKey,
+  LAMPORTS_PER_SOL,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+  SystemProgram,
+  Transaction,
+  sendAndConfirmTransaction,
+} from "@solana/web3.js";
+import {
+  ExtensionType,
+  TOKEN_2022_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  createInitializeMintInstruction,
+  createInitializeTransferFeeConfigInstruction,
+  createMint,
+  getMintLen,
+  getAssociatedTokenAddressSync,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+} from "@solana/spl-token";
+import { assert } from "chai";
+import { Amm } from "../target/types/amm";
+import { EscrowSolanaTurbin3 } from "../../escrow-solana-turbin3/target/types/escrow_solana_turbin3";
+
+describe("amm", () => {
+  // Configure the client to use the local cluster.
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.amm as Program<Amm>;
+  const connection = provider.connection;
+
+  const admin = Keypair.generate();
+  const user = Keypair.generate();
+  const seed = new BN(1);
+
+  let mintX: PublicKey;
+  let mintY: PublicKey;
+
+  const config = PublicKey.findProgramAddressSync(
+    [Buffer.from("config"), seed.toArrayLike(Buffer, "le", 8)],
+    program.programId
+  )[0];
+  const mintLp = PublicKey.findProgramAddressSync([Buffer.from("lp"), config.toBuffer()], program.programId)[0];
+
+  const balance = async (mint: PublicKey, owner: PublicKey) =>
+    Number((await connection.getTokenAccountBalance(getAssociatedTokenAddressSync(mint, owner, true))).value.amount);
+
+  const poolAccounts = () => ({
+    user: user.publicKey,
+    mintX,
+    mintY,
+    config,
+    vaultX: getAssociatedTokenAddressSync(mintX, config, true),
+    vaultY: getAssociatedTokenAddressSync(mintY, config, true),
+    userX: getAssociatedTokenAddressSync(mintX, user.publicKey),
+    userY: getAssociatedTokenAddressSync(mintY, user.publicKey),
+    tokenProgram: TOKEN_PROGRAM_ID,
+  });
+
+  before(async () => {
+    for (const kp of [admin, user]) {
+      const sig = await connection.requestAirdrop(kp.publicKey, 10 * LAMPORTS_PER_SOL);
+      await connection.confirmTransaction(sig);
+    }
+
+    mintX = await createMint(connection, admin, admin.publicKey, null, 6);
+    mintY = await createMint(connection, admin, admin.publicKey, null, 6);
+
+    for (const mint of [mintX, mintY]) {
+      const ata = await getOrCreateAssociatedTokenAccount(connection, user, mint, user.publicKey);
+      await mintTo(connection, admin, mint, ata.address, admin, 10_000_000);
+    }
+  });
+
+  it("initialize", async () => {
+    await program.methods
+      .initialize(seed, 30, admin.publicKey, { constantProduct: {} }, new BN(0))
+      .accountsPartial({
+        initializer: admin.publicKey,
+        mintX,
+        mintY,
+        config,
+        mintLp,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([admin])
+      .rpc();
+
+    const pool = await program.account.config.fetch(config);
+    assert.equal(pool.fee, 30);
+    assert.isFalse(pool.locked);
+  });
+
+  it("deposit", async () => {
+    // the first deposit mints sqrt(1_000_000 * 4_000_000) LP and locks 1_000 of it in the pool
+    try {
+      await program.methods
+        .deposit(new BN(2_000_000), new BN(1_000_000), new BN(4_000_000))
+        .accountsPartial({ ...poolAccounts(), mintLp })
+        .signers([user])
+        .rpc();
+      assert.fail("deposit should have been rejected");
+    } catch (err) {
+      assert.include(String(err), "SlippageExceeded");
+    }
+    await program.methods
+      .deposit(new BN(1_999_000), new BN(1_000_000), new BN(4_000_000))
+      .accountsPartial({ ...poolAccounts(), mintLp })
+      .signers([user])
+      .rpc();
+    assert.equal(await balance(mintLp, user.publicKey), 1_999_000);
+    assert.equal(await balance(mintLp, config), 1_000);
+
+    // a second deposit follows the pool ratio , 10% of the supply costs 10% of each reserve
+    await program.methods
+      .deposit(new BN(200_000), new BN(100_000), new BN(400_000))
+      .accountsPartial({ ...poolAccounts(), mintLp })
+      .signers([user])
+      .rpc();
+
+    assert.equal(await balance(mintX, config), 1_100_000);
+    assert.equal(await balance(mintY, config), 4_400_000);
+    assert.equal(await balance(mintLp, user.publicKey), 2_199_000);
+  });
+
+  it("mints with a transfer fee can't be pooled", async () => {
+    const feeMint = Keypair.generate();
+    const space = getMintLen([ExtensionType.TransferFeeConfig]);
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(
+        SystemProgram.createAccount({
+          fromPubkey: admin.publicKey,
+          newAccountPubkey: feeMint.publicKey,
+          space,
+          lamports: await connection.getMinimumBalanceForRentExemption(space),
+          programId: TOKEN_2022_PROGRAM_ID,
+        }),
+        createInitializeTransferFeeConfigInstruction(feeMint.publicKey, admin.publicKey, admin.publicKey, 100, BigInt(1_000_000), TOKEN_2022_PROGRAM_ID),
+        createInitializeMintInstruction(feeMint.publicKey, 6, admin.publicKey, null, TOKEN_2022_PROGRAM_ID)
+      ),
+      [admin, feeMint]
+    );
+    const plainMint = await createMint(connection, admin, admin.publicKey, null, 6, undefined, undefined, TOKEN_2022_PROGRAM_ID);
+
+    const feeSeed = new BN(9);
+    const feeConfig = PublicKey.findProgramAddressSync(
+      [Buffer.from("config"), feeSeed.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+    try {
+      await program.methods
+        .initialize(feeSeed, 30, null, { constantProduct: {} }, new BN(0))
+        .accountsPartial({
+          initializer: admin.publicKey,
+          mintX: feeMint.publicKey,
+          mintY: plainMint,
+          config: feeConfig,
+          mintLp: PublicKey.findProgramAddressSync([Buffer.from("lp"), feeConfig.toBuffer()], program.programId)[0],
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc();
+      assert.fail("initialize should have been rejected");
+    } catch (err) {
+      assert.include(String(err), "TransferFeeMint");
+    }
+  });
+
+  it("swap", async () => {
+    // 10_000 x in , 9_970 after the 0.3% fee , 4_400_000 * 9_970 / 1_109_970 = 39_521.7 rounded down
+    await program.methods
+      .swap(true, new BN(10_000), new BN(39_521))
+      .accountsPartial(poolAccounts())
+      .signers([user])
+      .rpc();
+
+    assert.equal(await balance(mintX, config), 1_110_000);
+    assert.equal(await balance(mintY, config), 4_400_000 - 39_521);
+
+    try {
+      await program.methods
+        .swap(true, new BN(10_000), new BN(1_000_000))
+        .accountsPartial(poolAccounts())
+        .signers([user])
+        .rpc();
+      assert.fail("swap should have been rejected");
+    } catch (err) {
+      assert.include(String(err), "SlippageExceeded");
+    }
+  });
+
+  it("lock stops swaps but not withdrawals", async () => {
+    await program.methods
+      .setLocked(true)
+      .accountsPartial({ authority: admin.publicKey, config })
+      .signers([admin])
+      .rpc();
+
+    try {
+      await program.methods
+        .swap(false, new BN(10_000), new BN(0))
+        .accountsPartial(poolAccounts())
+        .signers([user])
+        .rpc();
+      assert.fail("swap should have been rejected");
+    } catch (err) {
+      assert.include(String(err), "PoolLocked");
+    }
+
+    // all the user's LP is 2_199_000 of the 2_200_000 supply , the locked 1_000 keeps its share in the pool
+    await program.methods
+      .withdraw(new BN(2_199_000), new BN(1_109_495), new BN(4_358_496))
+      .accountsPartial({ ...poolAccounts(), mintLp })
+      .signers([user])
+      .rpc();
+
+    assert.equal(await balance(mintX, config), 1_110_000 - 1_109_495);
+    assert.equal(await balance(mintY, config), 4_400_000 - 39_521 - 4_358_496);
+  });
+
+  describe("stable swap", () => {
+    const stableSeed = new BN(2);
+    const stableConfig = PublicKey.findProgramAddressSync(
+      [Buffer.from("config"), stableSeed.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+    const stableLp = PublicKey.findProgramAddressSync([Buffer.from("lp"), stableConfig.toBuffer()], program.programId)[0];
+
+    const stableAccounts = () => ({
+      ...poolAccounts(),
+      config: stableConfig,
+      vaultX: getAssociatedTokenAddressSync(mintX, stableConfig, true),
+      vaultY: getAssociatedTokenAddressSync(mintY, stableConfig, true),
+    });
+
+    const meta = (pubkey: PublicKey, isWritable = true) => ({ pubkey, isWritable, isSigner: false });
+    // x -> y through the stable pool
+    const stableLeg = () => [
+      meta(stableConfig),
+      meta(stableAccounts().vaultX),
+      meta(stableAccounts().vaultY),
+      meta(mintX, false),
+      meta(mintY, false),
+      meta(getAssociatedTokenAddressSync(mintX, user.publicKey)),
+      meta(getAssociatedTokenAddressSync(mintY, user.publicKey)),
+      meta(TOKEN_PROGRAM_ID, false),
+    ];
+
+    it("initialize and deposit", async () => {
+      try {
+        await program.methods
+          .initialize(stableSeed, 30, admin.publicKey, { stableSwap: {} }, new BN(0))
+          .accountsPartial({ initializer: admin.publicKey, mintX, mintY, config: stableConfig, mintLp: stableLp, tokenProgram: TOKEN_PROGRAM_ID })
+          .signers([admin])
+          .rpc();
+        assert.fail("a zero amp should have been rejected");
+      } catch (err) {
+        assert.include(String(err), "InvalidAmp");
+      }
+
+      await program.methods
+        .initialize(stableSeed, 30, admin.publicKey, { stableSwap: {} }, new BN(100))
+        .accountsPartial({ initializer: admin.publicKey, mintX, mintY, config: stableConfig, mintLp: stableLp, tokenProgram: TOKEN_PROGRAM_ID })
+        .signers([admin])
+        .rpc();
+
+      await program.methods
+        .deposit(new BN(999_000), new BN(1_000_000), new BN(1_000_000))
+        .accountsPartial({ ...stableAccounts(), mintLp: stableLp })
+        .signers([user])
+        .rpc();
+
+      const pool = await program.account.config.fetch(stableConfig);
+      assert.deepEqual(pool.curve, { stableSwap: {} });
+      assert.equal(pool.amp.targetAmp.toNumber(), 100);
+    });
+
+    it("swap stays close to the peg", async () => {
+      // constant product would pay 9_871 here , amp 100 pays all but the fee and the rounding unit
+      await program.methods
+        .swap(true, new BN(10_000), new BN(9_969))
+        .accountsPartial(stableAccounts())
+        .signers([user])
+        .rpc();
+
+      assert.equal(await balance(mintX, stableConfig), 1_010_000);
+      assert.equal(await balance(mintY, stableConfig), 1_000_000 - 9_969);
+    });
+
+    it("price oracle", async () => {
+      const observations = PublicKey.findProgramAddressSync(
+        [Buffer.from("observations"), stableConfig.toBuffer()],
+        program.programId
+      )[0];
+
+      const pool = await program.account.config.fetch(stableConfig);
+      assert.isAbove(pool.lastUpdateTs.toNumber(), 0);
+
+      await program.methods
+        .initObservations()
+        .accountsPartial({ payer: user.publicKey, config: stableConfig, observations })
+        .signers([user])
+        .rpc();
+
+      const record = () =>
+        program.methods
+          .recordObservation()
+          .accountsPartial({
+            config: stableConfig,
+            vaultX: stableAccounts().vaultX,
+            vaultY: stableAccounts().vaultY,
+            observations,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc();
+
+      await record();
+      const recorded = await program.account.observations.fetch(observations);
+      assert.equal(recorded.observations.length, 1);
+      assert.equal(recorded.next, 1);
+
+      // the ring buffer only takes one observation a minute , so it can't be flushed by spam
+      try {
+        await record();
+        assert.fail("observation should have been rejected");
+      } catch (err) {
+        assert.include(String(err), "ObservationTooSoon");
+      }
+
+      // a single observation spans no time yet
+      try {
+        await program.methods.observe(new BN(60)).accountsPartial({ config: stableConfig, observations }).view();
+        assert.fail("observe should have been rejected");
+      } catch (err) {
+        assert.include(String(err), "NotEnoughObservations");
+      }
+    });
+
+    it("flash loan", async () => {
+      const flashAccounts = { ...stableAccounts(), instructions: SYSVAR_INSTRUCTIONS_PUBKEY };
+      const borrow = () => program.methods.flashBorrow(true, new BN(100_000)).accountsPartial(flashAccounts).instruction();
+      const repay = () => program.methods.flashRepay().accountsPartial(flashAccounts).instruction();
+      const send = async (...ixs: anchor.web3.TransactionInstruction[]) =>
+        provider.sendAndConfirm(new Transaction().add(...ixs), [user]);
+
+      try {
+        await send(await borrow());
+        assert.fail("a borrow without a repay should have been rejected");
+      } catch (err) {
+        assert.include(String(err), "MissingFlashRepay");
+      }
+
+      // the pool can't be swapped against while its vault is short
+      try {
+        const swap = await program.methods
+          .swap(false, new BN(1_000), new BN(0))
+          .accountsPartial(stableAccounts())
+          .instruction();
+        await send(await borrow(), swap, await repay());
+        assert.fail("swap should have been rejected");
+      } catch (err) {
+        assert.include(String(err), "FlashLoanActive");
+      }
+
+      const before = await balance(mintX, stableConfig);
+      await send(await borrow(), await repay());
+
+      // 0.3% of 100_000 stays in the vault for the LPs
+      assert.equal(await balance(mintX, stableConfig), before + 300);
+      assert.isNull((await program.account.config.fetch(stableConfig)).flashLoan);
+    });
+
+    it("ramp amp", async () => {
+      const now = Math.floor(Date.now() / 1000);
+
+      for (const [target, stop] of [
+        [200, now + 60], // shorter than a day
+        [2_000, now + 2 * 86_400], // more than 10x
+      ]) {
+        try {
+          await program.methods
+            .rampAmp(new BN(target), new BN(stop))
+            .accountsPartial({ authority: admin.publicKey, config: stableConfig })
+            .signers([admin])
+            .rpc();
+          assert.fail("ramp should have been rejected");
+        } catch (err) {
+          assert.include(String(err), "InvalidRamp");
+        }
+      }
+
+      await program.methods
+        .rampAmp(new BN(500), new BN(now + 2 * 86_400))
+        .accountsPartial({ authority: admin.publicKey, config: stableConfig })
+        .signers([admin])
+        .rpc();
+
+      let pool = await program.account.config.fetch(stableConfig);
+      assert.equal(pool.amp.initialAmp.toNumber(), 100);
+      assert.equal(pool.amp.targetAmp.toNumber(), 500);
+
+      // stopping straight away leaves the amp a few seconds into the ramp , still close to the start
+      await program.methods
+        .stopRampAmp()
+        .accountsPartial({ authority: admin.publicKey, config: stableConfig })
+        .signers([admin])
+        .rpc();
+
+      pool = await program.account.config.fetch(stableConfig);
+      assert.equal(pool.amp.initialAmp.toNumber(), pool.amp.targetAmp.toNumber());
+      assert.isBelow(pool.amp.targetAmp.toNumber(), 110);
+
+      try {
+        await program.methods
+          .rampAmp(new BN(200), new BN(now + 2 * 86_400))
+          .accountsPartial({ authority: admin.publicKey, config })
+          .signers([admin])
+          .rpc();
+        assert.fail("constant product pools can't ramp");
+      } catch (err) {
+        assert.include(String(err), "NotStablePool");
+      }
+    });
+
+    it("route across pools", async () => {
+      // a constant product y / z pool for the second hop
+      const mintZ = await createMint(connection, admin, admin.publicKey, null, 6);
+      const userZ = await getOrCreateAssociatedTokenAccount(connection, user, mintZ, user.publicKey);
+      await mintTo(connection, admin, mintZ, userZ.address, admin, 10_000_000);
+
+      const routeSeed = new BN(3);
+      const routeConfig = PublicKey.findProgramAddressSync(
+        [Buffer.from("config"), routeSeed.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+      const routeLp = PublicKey.findProgramAddressSync([Buffer.from("lp"), routeConfig.toBuffer()], program.programId)[0];
+      const routeVault = (mint: PublicKey) => getAssociatedTokenAddressSync(mint, routeConfig, true);
+
+      await program.methods
+        .initialize(routeSeed, 30, null, { constantProduct: {} }, new BN(0))
+        .accountsPartial({ initializer: admin.publicKey, mintX: mintY, mintY: mintZ, config: routeConfig, mintLp: routeLp, tokenProgram: TOKEN_PROGRAM_ID })
+        .signers([admin])
+        .rpc();
+      await program.methods
+        .deposit(new BN(1_413_213), new BN(1_000_000), new BN(2_000_000))
+        .accountsPartial({
+          user: user.publicKey,
+          mintX: mintY,
+          mintY: mintZ,
+          config: routeConfig,
+          mintLp: routeLp,
+          vaultX: routeVault(mintY),
+          vaultY: routeVault(mintZ),
+          userX: getAssociatedTokenAddressSync(mintY, user.publicKey),
+          userY: userZ.address,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user])
+        .rpc();
+
+      const productLeg = [
+        meta(routeConfig),
+        meta(routeVault(mintY)),
+        meta(routeVault(mintZ)),
+        meta(mintY, false),
+        meta(mintZ, false),
+        meta(getAssociatedTokenAddressSync(mintY, user.publicKey)),
+        meta(userZ.address),
+        meta(TOKEN_PROGRAM_ID, false),
+      ];
+      const route = (minOut: number, secondIsX: boolean) =>
+        program.methods
+          .route(new BN(10_000), new BN(minOut), [{ pool: { isX: true } }, { pool: { isX: secondIsX } }])
+          .accountsPartial({ user: user.publicKey })
+          .remainingAccounts([...stableLeg(), ...productLeg])
+          .signers([user])
+          .rpc();
+
+      // selling z on the second hop doesn't spend the y the first hop bought
+      try {
+        await route(0, false);
+        assert.fail("route should have been rejected");
+      } catch (err) {
+        assert.include(String(err), "RouteAccountMismatch");
+      }
+
+      // x -> y at roughly the peg , then y -> z at roughly 2 z per y , so 10_000 x can't return 20_000 z
+      try {
+        await route(20_000, true);
+        assert.fail("route should have been rejected");
+      } catch (err) {
+        assert.include(String(err), "SlippageExceeded");
+      }
+
+      const before = await balance(mintZ, user.publicKey);
+      await route(19_000, true);
+      const received = (await balance(mintZ, user.publicKey)) - before;
+      assert.isAtLeast(received, 19_000);
+      assert.isBelow(received, 20_000);
+    });
+
+    it("deposit single", async () => {
+      const zapAccounts = {
+        user: user.publicKey,
+        mintIn: mintX,
+        mintOut: mintY,
+        config: stableConfig,
+        mintLp: stableLp,
+        vaultIn: stableAccounts().vaultX,
+        vaultOut: stableAccounts().vaultY,
+        userIn: getAssociatedTokenAddressSync(mintX, user.publicKey),
+        tokenProgram: TOKEN_PROGRAM_ID,
+      };
+
+      // about 2_000_000 tokens back the 1_000_000 LP , so 50_000 x is worth a little under 25_000 LP , never more
+      try {
+        await program.methods.depositSingle(new BN(50_000), new BN(25_000)).accountsPartial(zapAccounts).signers([user]).rpc();
+        assert.fail("deposit should have been rejected");
+      } catch (err) {
+        assert.include(String(err), "SlippageExceeded");
+      }
+
+      const [vaultX, vaultY, lp] = await Promise.all([
+        balance(mintX, stableConfig),
+        balance(mintY, stableConfig),
+        balance(stableLp, user.publicKey),
+      ]);
+      await program.methods.depositSingle(new BN(50_000), new BN(24_000)).accountsPartial(zapAccounts).signers([user]).rpc();
+
+      // all of the x lands in the vault , the y bought by the internal swap never leaves it
+      assert.equal(await balance(mintX, stableConfig), vaultX + 50_000);
+      assert.equal(await balance(mintY, stableConfig), vaultY);
+      const minted = (await balance(stableLp, user.publicKey)) - lp;
+      assert.isAtLeast(minted, 24_000);
+      assert.isBelow(minted, 25_000);
+    });
+
+    it("route through an escrow order", async () => {
+      const escrowProgram = new Program<EscrowSolanaTurbin3>(
+        require("../../escrow-solana-turbin3/target/idl/escrow_solana_turbin3.json"),
+        provider
+      );
+      const escrowPda = (...seeds: Buffer[]) => PublicKey.findProgramAddressSync(seeds, escrowProgram.programId)[0];
+      const treasury = Keypair.generate();
+      const feeConfig = escrowPda(Buffer.from("config"));
+      await escrowProgram.methods
+        .initConfig(treasury.publicKey, 100, 5_000)
+        .accountsPartial({
+          admin: provider.wallet.publicKey,
+          config: feeConfig,
+          programData: PublicKey.findProgramAddressSync(
+            [escrowProgram.programId.toBuffer()],
+            new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+          )[0],
+        })
+        .rpc();
+      const treasuryY = (await getOrCreateAssociatedTokenAccount(connection, admin, mintY, treasury.publicKey)).address;
+
+      // the admin sells w for y , 1_000 w for 10_000 y
+      const mintW = await createMint(connection, admin, admin.publicKey, null, 6);
+      const adminW = await getOrCreateAssociatedTokenAccount(connection, admin, mintW, admin.publicKey);
+      await mintTo(connection, admin, mintW, adminW.address, admin, 10_000);
+      const orderBook = escrowPda(Buffer.from("orderbook"), mintW.toBuffer(), mintY.toBuffer());
+      await escrowProgram.methods
+        .initOrderBook(new BN(1))
+        .accountsPartial({ admin: provider.wallet.publicKey, config: feeConfig, mintA: mintW, mintB: mintY, orderBook })
+        .rpc();
+
+      const makerProfile = escrowPda(Buffer.from("profile"), admin.publicKey.toBuffer());
+      const makeOrder = async (book: PublicKey | null) => {
+        const profile = await escrowProgram.account.makerProfile.fetchNullable(makerProfile);
+        const seed = profile ? profile.escrowCount : new BN(0);
+        const escrow = escrowPda(Buffer.from("escrow"), admin.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8));
+        await escrowProgram.methods
+          .make(new BN(1_000), new BN(10_000), null, null, new BN(0))
+          .accountsPartial({
+            maker: admin.publicKey,
+            mintA: mintW,
+            mintB: mintY,
+            makerAtaA: adminW.address,
+            makerProfile,
+            escrow,
+            vault: getAssociatedTokenAddressSync(mintW, escrow, true),
+            orderBook: book,
+            tokenProgramA: TOKEN_PROGRAM_ID,
+            tokenProgramB: TOKEN_PROGRAM_ID,
+          })
+          .signers([admin])
+          .rpc();
+        return escrow;
+      };
+
+      // (maker , mint a , mint b , user ATA b , user ATA a , maker ATA b , escrow , vault , fee config ,
+      //  treasury ATA b , order book , token program a , token program b)
+      const escrowLeg = (escrow: PublicKey, userAtaB: PublicKey, book: PublicKey) => [
+        meta(admin.publicKey),
+        meta(mintW, false),
+        meta(mintY, false),
+        meta(userAtaB),
+        meta(getAssociatedTokenAddressSync(mintW, user.publicKey)),
+        meta(getAssociatedTokenAddressSync(mintY, admin.publicKey)),
+        meta(escrow),
+        meta(getAssociatedTokenAddressSync(mintW, escrow, true)),
+        meta(feeConfig, false),
+        meta(treasuryY),
+        meta(book, !book.equals(escrowProgram.programId)),
+        meta(TOKEN_PROGRAM_ID, false),
+        meta(TOKEN_PROGRAM_ID, false),
+      ];
+      const userY = getAssociatedTokenAddressSync(mintY, user.publicKey);
+      const route = (leg: ReturnType<typeof escrowLeg>, minOut = 0) =>
+        program.methods
+          .route(new BN(10_000), new BN(minOut), [{ pool: { isX: true } }, { escrowOrder: {} }])
+          .accountsPartial({ user: user.publicKey, escrowProgram: escrowProgram.programId })
+          .remainingAccounts([...stableLeg(), ...leg])
+          .signers([user])
+          .rpc();
+
+      // both programs share error names , so the failing source file is checked as well
+      const expectFailure = async (promise: Promise<unknown>, source: string, code: string) => {
+        try {
+          await promise;
+          assert.fail(`expected ${code}`);
+        } catch (err) {
+          const lines = [String(err), ...(err.logs ?? [])];
+          assert.isTrue(lines.some((line) => line.includes(source) && line.includes(code)), `expected ${code} from ${source}`);
+        }
+      };
+
+      // the escrow program id stands in for the order book of an unlisted escrow
+      const unlisted = await makeOrder(null);
+      const placeholder = escrowProgram.programId;
+
+      // the escrow leg has to pay out of the y ATA the stable leg paid into
+      await expectFailure(
+        route(escrowLeg(unlisted, getAssociatedTokenAddressSync(mintX, user.publicKey), placeholder)),
+        "instructions/route.rs",
+        "RouteAccountMismatch"
+      );
+
+      // 10_000 x buys a little under 10_000 y , too little for all 1_000 w
+      await expectFailure(route(escrowLeg(unlisted, userY, placeholder), 1_000), "instructions/route.rs", "SlippageExceeded");
+
+      // the fill is sized from what the stable leg paid out , at 10 y per w
+      // the failed routes left nothing behind , the fill creates the user's w ATA and the maker's y ATA
+      assert.isNull(await connection.getAccountInfo(getAssociatedTokenAddressSync(mintW, user.publicKey)));
+      assert.isNull(await connection.getAccountInfo(getAssociatedTokenAddressSync(mintY, admin.publicKey)));
+      const yBefore = await balance(mintY, user.publicKey);
+      await route(escrowLeg(unlisted, userY, placeholder), 990);
+      const filled = await balance(mintW, user.publicKey);
+      assert.isAtLeast(filled, 990);
+      assert.isBelow(filled, 1_000);
+      // 1% of the price goes to the treasury , the maker gets the rest
+      const price = filled * 10;
+      assert.equal(await balance(mintY, treasury.publicKey), Math.floor(price / 100));
+      assert.equal(await balance(mintY, admin.publicKey), price - Math.floor(price / 100));
+      // less than the price of one w is left over
+      const leftover = (await balance(mintY, user.publicKey)) - yBefore;
+      assert.isAtLeast(leftover, 0);
+      assert.isBelow(leftover, 10);
+
+      // a second route is capped at what is left in the escrow , which closes once filled
+      await route(escrowLeg(unlisted, userY, placeholder), 1_000 - filled);
+      assert.equal(await balance(mintW, user.publicKey), 1_000);
+      assert.isNull(await connection.getAccountInfo(unlisted));
+
+      // a listed escrow can't be filled with the placeholder , only through its book
+      const listed = await makeOrder(orderBook);
+      await expectFailure(route(escrowLeg(listed, userY, placeholder)), "src/state.rs", "OrderBookRequired");
+      const wBefore = await balance(mintW, user.publicKey);
+      await route(escrowLeg(listed, userY, orderBook), 990);
+      const listedFill = (await balance(mintW, user.publicKey)) - wBefore;
+      const book = await escrowProgram.account.orderBook.fetch(orderBook);
+      assert.equal(book.entries[0].remainingAmount.toNumber(), 1_000 - listedFill);
+    });
+  });
+});
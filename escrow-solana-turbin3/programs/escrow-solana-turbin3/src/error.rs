@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Fill amount is larger than what is left in the escrow")]
+    FillTooLarge,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Taker is not allowed to take this escrow")]
+    TakerNotAllowed,
+    #[msg("Escrow has expired")]
+    EscrowExpired,
+    #[msg("Expiry must be zero or in the future")]
+    InvalidExpiry,
+    #[msg("Auction prices must decay to a non zero end price over a non empty window")]
+    InvalidAuction,
+    #[msg("Auction orders can't be repriced")]
+    AuctionPriceFixed,
+    #[msg("Price is above the taker's max_pay")]
+    SlippageExceeded,
+    #[msg("Basket needs between one and MAX_BASKET_LEGS distinct mints on each side")]
+    InvalidBasket,
+    #[msg("Basket accounts don't match the basket legs")]
+    BasketAccountMismatch,
+    #[msg("Route accounts must come in (escrow, vault, maker ATA b, maker) tuples matching the mints")]
+    RouteAccountMismatch,
+    #[msg("Route doesn't hold enough token a for the target amount")]
+    InsufficientLiquidity,
+    #[msg("Escrow doesn't ask for an NFT collection")]
+    NotCollectionOrder,
+    #[msg("Collection orders always ask for a single NFT and can't be repriced")]
+    CollectionPriceFixed,
+    #[msg("Mint must have zero decimals and a supply of one")]
+    NotAnNft,
+    #[msg("NFT is not a verified member of the requested collection")]
+    CollectionMismatch,
+    #[msg("Escrow is not paid in SOL")]
+    NotSolOrder,
+    #[msg("Fee can't exceed MAX_FEE_BPS and the referrer share can't exceed 100%")]
+    InvalidFeeConfig,
+    #[msg("Takers can't refer themselves")]
+    SelfReferral,
+    #[msg("Signer is not allowed to manage the fee config")]
+    Unauthorized,
+    #[msg("Order book is for another mint pair")]
+    OrderBookMismatch,
+    #[msg("Escrow is listed in its order book , pass the order book account")]
+    OrderBookRequired,
+    #[msg("Fee must be paid to the config treasury , pass its account")]
+    TreasuryMismatch,
+    #[msg("Listing pushes the worst escrow out of the full order book , pass it as `evicted`")]
+    EvictedEscrowRequired,
+}
@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+use crate::error::ErrorCode;
+use crate::state::{sync_order_book, Escrow, OrderBook};
+
+// takers watch this to see price changes without polling every escrow
+#[event]
+pub struct EscrowAmended {
+    pub escrow : Pubkey,
+    pub maker : Pubkey,
+    pub recieve_amount : u64,
+    pub expires_at : i64,
+}
+
+#[derive(Accounts)]
+pub struct Amend<'info>{
+    pub maker : Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = maker,
+        seeds= [b"escrow",maker.key().as_ref(),escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow : Account<'info,Escrow>,
+
+    // the pair's order book , required once the escrow is listed in it
+    #[account(mut)]
+    pub order_book : Option<Account<'info,OrderBook>>,
+}
+
+impl<'info> Amend<'info>{
+    pub fn amend(&mut self , recieve_amount : Option<u64> , expires_at : Option<i64>) -> Result<()>{
+
+        if let Some(recieve_amount) = recieve_amount {
+            require!(recieve_amount > 0, ErrorCode::InvalidAmount);
+            require!(self.escrow.auction.is_none(), ErrorCode::AuctionPriceFixed);
+            require!(self.escrow.collection.is_none(), ErrorCode::CollectionPriceFixed);
+            self.escrow.recieve_amount = recieve_amount;
+        }
+
+        if let Some(expires_at) = expires_at {
+            require!(
+                expires_at == 0 || expires_at > Clock::get()?.unix_timestamp,
+                ErrorCode::InvalidExpiry
+            );
+            self.escrow.expires_at = expires_at;
+        }
+
+        let key = self.escrow.key();
+        sync_order_book(self.order_book.as_deref_mut(), key, &mut self.escrow, Clock::get()?.unix_timestamp)?;
+
+        emit!(EscrowAmended {
+            escrow: self.escrow.key(),
+            maker: self.maker.key(),
+            recieve_amount: self.escrow.recieve_amount,
+            expires_at: self.escrow.expires_at,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,301 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{create_idempotent, get_associated_token_address_with_program_id, AssociatedToken, Create},
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked
+    }
+};
+use crate::error::ErrorCode;
+use crate::state::{Basket, BasketLeg, MakerProfile, MAX_BASKET_LEGS};
+use crate::utils::amount_with_transfer_fee;
+
+// every leg is passed as four accounts in `remaining_accounts`
+pub const BASKET_LEG_ACCOUNTS: usize = 4;
+
+// make_basket  : per offered leg   (mint , token program , maker ATA , vault)
+// take_basket  : per offered leg   (mint , token program , vault , taker ATA)
+//                then per requested leg (mint , token program , taker ATA , maker ATA)
+// refund_basket: per offered leg   (mint , token program , vault , maker ATA)
+// each leg names the token program owning its mint , so spl token and token 2022 mints can share a basket
+
+#[derive(Accounts)]
+pub struct MakeBasket<'info> {
+    #[account(mut)]
+    pub maker : Signer<'info>,
+
+    // baskets share the maker's escrow counter
+    #[account(
+        init_if_needed,
+        payer = maker,
+        seeds= [b"profile",maker.key().as_ref()],
+        bump,
+        space = 8 + MakerProfile::INIT_SPACE
+    )]
+    pub maker_profile : Account<'info,MakerProfile>,
+
+    #[account(
+        init,
+        payer = maker,
+        seeds= [b"basket",maker.key().as_ref(),maker_profile.escrow_count.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + Basket::INIT_SPACE
+    )]
+    pub basket : Account<'info,Basket>,
+
+    pub associated_token_program : Program<'info,AssociatedToken>,
+    pub system_program : Program<'info , System>,
+}
+
+#[derive(Accounts)]
+pub struct TakeBasket<'info> {
+    #[account(mut)]
+    pub taker : Signer<'info>,
+
+    #[account(mut)]
+    pub maker : SystemAccount<'info>,
+
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        seeds= [b"basket",maker.key().as_ref(),basket.seed.to_le_bytes().as_ref()],
+        bump = basket.bump,
+    )]
+    pub basket : Account<'info,Basket>,
+
+    pub associated_token_program : Program<'info,AssociatedToken>,
+    pub system_program : Program<'info , System>,
+}
+
+#[derive(Accounts)]
+pub struct RefundBasket<'info> {
+    #[account(mut)]
+    pub maker : Signer<'info>,
+
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        seeds= [b"basket",maker.key().as_ref(),basket.seed.to_le_bytes().as_ref()],
+        bump = basket.bump,
+    )]
+    pub basket : Account<'info,Basket>,
+}
+
+fn check_legs(legs : &[BasketLeg]) -> Result<()> {
+    require!(!legs.is_empty() && legs.len() <= MAX_BASKET_LEGS, ErrorCode::InvalidBasket);
+    for (i, leg) in legs.iter().enumerate() {
+        require!(leg.amount > 0, ErrorCode::InvalidAmount);
+        require!(legs[..i].iter().all(|other| other.mint != leg.mint), ErrorCode::InvalidBasket);
+    }
+    Ok(())
+}
+
+fn check_ata(account : &AccountInfo, owner : &Pubkey, mint : &Pubkey, token_program : &Pubkey) -> Result<()> {
+    let expected = get_associated_token_address_with_program_id(owner, mint, token_program);
+    require_keys_eq!(account.key(), expected, ErrorCode::BasketAccountMismatch);
+    Ok(())
+}
+
+// the first two accounts of a leg , its mint and the token program that owns it
+fn load_leg<'info>(
+    leg_accounts : &'info [AccountInfo<'info>],
+    leg : &BasketLeg
+) -> Result<(InterfaceAccount<'info, Mint>, Interface<'info, TokenInterface>)> {
+    let token_program = Interface::<TokenInterface>::try_from(&leg_accounts[1])
+        .map_err(|_| error!(ErrorCode::BasketAccountMismatch))?;
+    require_keys_eq!(leg_accounts[0].key(), leg.mint, ErrorCode::BasketAccountMismatch);
+    require_keys_eq!(*leg_accounts[0].owner, token_program.key(), ErrorCode::BasketAccountMismatch);
+    Ok((InterfaceAccount::<Mint>::try_from(&leg_accounts[0])?, token_program))
+}
+
+impl<'info> MakeBasket<'info> {
+    pub fn make_basket(
+        &mut self ,
+        remaining : &'info [AccountInfo<'info>] ,
+        offered_amounts : Vec<u64> ,
+        requested : Vec<BasketLeg> ,
+        bumps : &MakeBasketBumps
+    ) -> Result<()> {
+        require!(remaining.len() == offered_amounts.len() * BASKET_LEG_ACCOUNTS, ErrorCode::BasketAccountMismatch);
+
+        let offered : Vec<BasketLeg> = remaining
+            .chunks(BASKET_LEG_ACCOUNTS)
+            .zip(offered_amounts.iter())
+            .map(|(accounts, amount)| BasketLeg { mint: accounts[0].key(), amount: *amount })
+            .collect();
+        check_legs(&offered)?;
+        check_legs(&requested)?;
+
+        for (leg_accounts, leg) in remaining.chunks(BASKET_LEG_ACCOUNTS).zip(offered.iter()) {
+            let (mint, token_program) = load_leg(leg_accounts, leg)?;
+            let maker_ata = &leg_accounts[2];
+            let vault = &leg_accounts[3];
+            check_ata(vault, &self.basket.key(), &leg.mint, &token_program.key())?;
+
+            create_idempotent(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                Create {
+                    payer: self.maker.to_account_info(),
+                    associated_token: vault.clone(),
+                    authority: self.basket.to_account_info(),
+                    mint: mint.to_account_info(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: token_program.to_account_info(),
+                },
+            ))?;
+
+            let transfer_accounts = TransferChecked{
+                from : maker_ata.clone(),
+                mint : mint.to_account_info(),
+                to : vault.clone(),
+                authority : self.maker.to_account_info()
+            };
+            let cpi_ctx = CpiContext::new(token_program.to_account_info(), transfer_accounts);
+            transfer_checked(cpi_ctx, leg.amount, mint.decimals)?;
+        }
+
+        let seed = self.maker_profile.next_seed(self.maker.key(), bumps.maker_profile)?;
+
+        self.basket.set_inner(Basket {
+            seed,
+            maker: self.maker.key(),
+            offered,
+            requested,
+            bump: bumps.basket,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> TakeBasket<'info> {
+    pub fn take_basket(&mut self , remaining : &'info [AccountInfo<'info>]) -> Result<()> {
+        let basket = &self.basket;
+
+        let offered_len = basket.offered.len() * BASKET_LEG_ACCOUNTS;
+        let requested_len = basket.requested.len() * BASKET_LEG_ACCOUNTS;
+        require!(remaining.len() == offered_len + requested_len, ErrorCode::BasketAccountMismatch);
+        let (offered_accounts, requested_accounts) = remaining.split_at(offered_len);
+
+        // taker pays every requested leg first , the maker ATAs are created if needed
+        for (leg_accounts, leg) in requested_accounts.chunks(BASKET_LEG_ACCOUNTS).zip(basket.requested.iter()) {
+            let (mint, token_program) = load_leg(leg_accounts, leg)?;
+            let taker_ata = &leg_accounts[2];
+            let maker_ata = &leg_accounts[3];
+            check_ata(maker_ata, &basket.maker, &leg.mint, &token_program.key())?;
+
+            create_idempotent(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                Create {
+                    payer: self.taker.to_account_info(),
+                    associated_token: maker_ata.clone(),
+                    authority: self.maker.to_account_info(),
+                    mint: mint.to_account_info(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: token_program.to_account_info(),
+                },
+            ))?;
+
+            let transfer_accounts = TransferChecked{
+                from : taker_ata.clone(),
+                mint : mint.to_account_info(),
+                to : maker_ata.clone(),
+                authority : self.taker.to_account_info()
+            };
+            let cpi_ctx = CpiContext::new(token_program.to_account_info(), transfer_accounts);
+            transfer_checked(cpi_ctx, amount_with_transfer_fee(&mint, leg.amount)?, mint.decimals)?;
+        }
+
+        let signer_seeds :[&[&[u8]];1]=
+        [&[
+            b"basket",
+            basket.maker.as_ref(),
+            &basket.seed.to_le_bytes()[..],
+            &[basket.bump]
+        ]];
+
+        // then every vault is emptied into the taker's ATA and closed , any failure reverts the whole swap
+        for (leg_accounts, leg) in offered_accounts.chunks(BASKET_LEG_ACCOUNTS).zip(basket.offered.iter()) {
+            let (mint, token_program) = load_leg(leg_accounts, leg)?;
+            let vault = &leg_accounts[2];
+            let taker_ata = &leg_accounts[3];
+            check_ata(vault, &basket.key(), &leg.mint, &token_program.key())?;
+            check_ata(taker_ata, &self.taker.key(), &leg.mint, &token_program.key())?;
+
+            create_idempotent(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                Create {
+                    payer: self.taker.to_account_info(),
+                    associated_token: taker_ata.clone(),
+                    authority: self.taker.to_account_info(),
+                    mint: mint.to_account_info(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: token_program.to_account_info(),
+                },
+            ))?;
+
+            empty_and_close_vault(token_program.to_account_info(), &mint, vault, taker_ata, self.maker.to_account_info(), basket.to_account_info(), &signer_seeds)?;
+        }
+
+        // basket account itself is closed by `close = maker`
+        Ok(())
+    }
+}
+
+impl<'info> RefundBasket<'info> {
+    pub fn refund_basket(&mut self , remaining : &'info [AccountInfo<'info>]) -> Result<()> {
+        let basket = &self.basket;
+
+        require!(
+            remaining.len() == basket.offered.len() * BASKET_LEG_ACCOUNTS,
+            ErrorCode::BasketAccountMismatch
+        );
+
+        let signer_seeds :[&[&[u8]];1]=
+        [&[
+            b"basket",
+            basket.maker.as_ref(),
+            &basket.seed.to_le_bytes()[..],
+            &[basket.bump]
+        ]];
+
+        for (leg_accounts, leg) in remaining.chunks(BASKET_LEG_ACCOUNTS).zip(basket.offered.iter()) {
+            let (mint, token_program) = load_leg(leg_accounts, leg)?;
+            let vault = &leg_accounts[2];
+            check_ata(vault, &basket.key(), &leg.mint, &token_program.key())?;
+
+            empty_and_close_vault(token_program.to_account_info(), &mint, vault, &leg_accounts[3], self.maker.to_account_info(), basket.to_account_info(), &signer_seeds)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn empty_and_close_vault<'info>(
+    token_program : AccountInfo<'info>,
+    mint : &InterfaceAccount<'info, Mint>,
+    vault : &'info AccountInfo<'info>,
+    to : &AccountInfo<'info>,
+    rent_destination : AccountInfo<'info>,
+    basket : AccountInfo<'info>,
+    signer_seeds : &[&[&[u8]]]
+) -> Result<()> {
+    let vault_amount = InterfaceAccount::<TokenAccount>::try_from(vault)?.amount;
+
+    let tranfer_accounts = TransferChecked{
+        from:vault.clone(),
+        mint:mint.to_account_info(),
+        to:to.clone(),
+        authority:basket.clone()
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.clone(), tranfer_accounts, signer_seeds);
+    transfer_checked(cpi_ctx, vault_amount, mint.decimals)?;
+
+    let close_accounts = CloseAccount{
+        account : vault.clone(),
+        destination:rent_destination,
+        authority:basket,
+    };
+    let close_cpi_ctx = CpiContext::new_with_signer(token_program, close_accounts, signer_seeds);
+    close_account(close_cpi_ctx)
+}
@@ -0,0 +1,237 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken, token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked
+    }
+};
+use crate::error::ErrorCode;
+use crate::state::{Escrow, MakerProfile, PaymentKind};
+use crate::utils::verify_collection;
+
+// the maker deposits token a and asks for any one NFT of `collection` instead of a fixed mint b
+#[derive(Accounts)]
+pub struct MakeCollectionOffer<'info> {
+    #[account(mut)]
+    pub maker : Signer<'info>,
+
+    #[account(
+        mint::token_program = token_program_a
+    )]
+    pub mint_a : InterfaceAccount<'info , Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_a
+    )]
+    pub maker_ata_a : InterfaceAccount<'info , TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = maker,
+        seeds= [b"profile",maker.key().as_ref()],
+        bump,
+        space = 8 + MakerProfile::INIT_SPACE
+    )]
+    pub maker_profile : Account<'info,MakerProfile>,
+
+    #[account(
+        init,
+        payer = maker,
+        seeds= [b"escrow",maker.key().as_ref(),maker_profile.escrow_count.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + Escrow::INIT_SPACE
+    )]
+    pub escrow: Account<'info,Escrow>,
+
+    #[account(
+        init ,
+        payer = maker,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program_a
+    )]
+    pub vault : InterfaceAccount<'info , TokenAccount>,
+
+    pub associated_token_program : Program<'info,AssociatedToken>,
+    pub system_program : Program<'info , System>,
+    pub token_program_a : Interface<'info , TokenInterface>
+}
+
+#[derive(Accounts)]
+pub struct TakeCollectionOffer<'info> {
+    #[account(mut)]
+    pub taker : Signer<'info>,
+
+    #[account(mut)]
+    pub maker : SystemAccount<'info>,
+
+    #[account(
+        mint::token_program = token_program_a
+    )]
+    pub mint_a : InterfaceAccount<'info , Mint>,
+
+    // the NFT the taker hands over , checked against `escrow.collection` in `verify_collection`
+    #[account(
+        mint::token_program = token_program_nft
+    )]
+    pub nft_mint : InterfaceAccount<'info , Mint>,
+
+    /// CHECK: only needed for NFTs without a token 2022 group member extension,
+    /// owner , address and layout are checked in `verify_collection`
+    pub nft_metadata : Option<UncheckedAccount<'info>>,
+
+    #[account(
+        mut ,
+        associated_token::mint = nft_mint,
+        associated_token::token_program = token_program_nft,
+        associated_token::authority  = taker
+    )]
+    pub taker_nft_ata : InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = nft_mint,
+        associated_token::token_program = token_program_nft,
+        associated_token::authority  = maker
+    )]
+    pub maker_nft_ata : InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_a,
+        associated_token::token_program = token_program_a,
+        associated_token::authority  = taker
+    )]
+    pub taker_ata_a : InterfaceAccount<'info, TokenAccount>,
+
+    // one NFT buys the whole deposit , so the escrow always closes here
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        has_one = mint_a,
+        constraint = escrow.collection.is_some() @ ErrorCode::NotCollectionOrder,
+        seeds= [b"escrow",maker.key().as_ref(),escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow : Account<'info,Escrow>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program_a
+    )]
+    pub vault : InterfaceAccount<'info , TokenAccount>,
+
+    pub associated_token_program : Program<'info , AssociatedToken>,
+    pub system_program : Program<'info , System>,
+    pub token_program_a : Interface<'info , TokenInterface>,
+    pub token_program_nft : Interface<'info , TokenInterface>
+}
+
+impl<'info> MakeCollectionOffer<'info> {
+    pub fn make_collection_offer(
+        &mut self ,
+        deposit_amount : u64 ,
+        collection : Pubkey ,
+        expires_at : i64 ,
+        bumps : &MakeCollectionOfferBumps
+    ) -> Result<()> {
+        require!(deposit_amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            expires_at == 0 || expires_at > Clock::get()?.unix_timestamp,
+            ErrorCode::InvalidExpiry
+        );
+
+        let seed = self.maker_profile.next_seed(self.maker.key(), bumps.maker_profile)?;
+
+        self.escrow.set_inner(Escrow {
+            seed,
+            maker: self.maker.key(),
+            mint_a: self.mint_a.key(),
+            mint_b: Pubkey::default(),
+            payment: PaymentKind::Token,
+            recieve_amount: 1,
+            deposit_amount,
+            remaining_amount: deposit_amount,
+            allowed_taker: None,
+            allowlist_root: None,
+            expires_at,
+            auction: None,
+            collection: Some(collection),
+            indexed: false,
+            bump: bumps.escrow,
+        });
+
+        let transfer_accounts = TransferChecked{
+            from : self.maker_ata_a.to_account_info(),
+            mint : self.mint_a.to_account_info(),
+            to : self.vault.to_account_info(),
+            authority : self.maker.to_account_info()
+        };
+        let cpi_ctx = CpiContext::new(self.token_program_a.to_account_info(), transfer_accounts);
+        transfer_checked(cpi_ctx, deposit_amount, self.mint_a.decimals)?;
+
+        // same as `Make::deposit` , only offer what actually arrived after a transfer fee
+        self.vault.reload()?;
+        self.escrow.deposit_amount = self.vault.amount;
+        self.escrow.remaining_amount = self.vault.amount;
+        Ok(())
+    }
+}
+
+impl<'info> TakeCollectionOffer<'info> {
+    // NFT goes to the maker , the whole vault goes to the taker
+    pub fn take_collection_offer(&mut self) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(!self.escrow.is_expired(now), ErrorCode::EscrowExpired);
+        let collection = self.escrow.collection.ok_or(ErrorCode::NotCollectionOrder)?;
+
+        let metadata = self.nft_metadata.as_ref().map(|metadata| metadata.to_account_info());
+        verify_collection(&self.nft_mint, metadata.as_ref(), &collection)?;
+
+        let nft_accounts = TransferChecked{
+            from:self.taker_nft_ata.to_account_info(),
+            mint:self.nft_mint.to_account_info(),
+            to:self.maker_nft_ata.to_account_info(),
+            authority:self.taker.to_account_info()
+        };
+        let cpi_ctx = CpiContext::new(self.token_program_nft.to_account_info(), nft_accounts);
+        transfer_checked(cpi_ctx, 1, self.nft_mint.decimals)?;
+
+        let amount = self.escrow.remaining_amount;
+        self.escrow.fill(amount)?;
+
+        let signer_seeds :[&[&[u8]];1]=
+        [&[
+            b"escrow",
+            self.maker.key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump]
+        ]];
+
+        let withdraw_accounts = TransferChecked{
+            from:self.vault.to_account_info(),
+            mint:self.mint_a.to_account_info(),
+            to:self.taker_ata_a.to_account_info(),
+            authority:self.escrow.to_account_info()
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program_a.to_account_info(), withdraw_accounts, &signer_seeds);
+        transfer_checked(cpi_ctx, amount, self.mint_a.decimals)?;
+
+        let close_accounts = CloseAccount{
+            account : self.vault.to_account_info(),
+            destination:self.maker.to_account_info(),
+            authority:self.escrow.to_account_info(),
+        };
+        let close_cpi_ctx = CpiContext::new_with_signer(self.token_program_a.to_account_info(), close_accounts, &signer_seeds);
+
+        // escrow itself is closed by `close = maker`
+        close_account(close_cpi_ctx)
+    }
+}
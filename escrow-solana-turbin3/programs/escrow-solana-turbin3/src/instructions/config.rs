@@ -0,0 +1,77 @@
+use anchor_lang::prelude::*;
+use crate::error::ErrorCode;
+use crate::program::EscrowSolanaTurbin3;
+use crate::state::FeeConfig;
+
+// only the upgrade authority of the program can create the config , so nobody can front run it
+#[derive(Accounts)]
+pub struct InitConfig<'info> {
+    #[account(mut)]
+    pub admin : Signer<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds= [b"config"],
+        bump,
+        space = 8 + FeeConfig::INIT_SPACE
+    )]
+    pub config : Account<'info,FeeConfig>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program : Program<'info , EscrowSolanaTurbin3>,
+
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ ErrorCode::Unauthorized)]
+    pub program_data : Account<'info , ProgramData>,
+
+    pub system_program : Program<'info , System>
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub admin : Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = admin @ ErrorCode::Unauthorized,
+        seeds= [b"config"],
+        bump = config.bump,
+    )]
+    pub config : Account<'info,FeeConfig>,
+}
+
+impl<'info> InitConfig<'info> {
+    pub fn init_config(
+        &mut self ,
+        treasury : Pubkey ,
+        fee_bps : u16 ,
+        referrer_share_bps : u16 ,
+        bumps : &InitConfigBumps
+    ) -> Result<()> {
+        self.config.set_inner(FeeConfig {
+            admin: self.admin.key(),
+            treasury,
+            fee_bps,
+            referrer_share_bps,
+            bump: bumps.config,
+        });
+        self.config.validate()
+    }
+}
+
+impl<'info> UpdateConfig<'info> {
+    // `new_admin` hands the config over , the old admin loses access right away
+    pub fn update_config(
+        &mut self ,
+        new_admin : Pubkey ,
+        treasury : Pubkey ,
+        fee_bps : u16 ,
+        referrer_share_bps : u16
+    ) -> Result<()> {
+        self.config.admin = new_admin;
+        self.config.treasury = treasury;
+        self.config.fee_bps = fee_bps;
+        self.config.referrer_share_bps = referrer_share_bps;
+        self.config.validate()
+    }
+}
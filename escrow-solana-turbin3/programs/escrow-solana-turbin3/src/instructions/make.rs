@@ -3,13 +3,12 @@ use anchor_spl::{associated_token::AssociatedToken,
 token_interface::{TokenAccount , TokenInterface ,
      Mint , TransferChecked , transfer_checked}
 };
-use crate::state::Escrow;
+use crate::error::ErrorCode;
+use crate::state::{DutchAuction, Escrow, MakerProfile, OrderBook, OrderBookEntry, PaymentKind};
 
 // all the account that we need to make this instruction happen.
 
 #[derive(Accounts)]
-#[instruction(seed:u64)]
-// so that i can use the seed paased in the escrow pda to be used as parament in ctx. 
 pub struct Make<'info> {
     #[account(mut)]
     pub maker : Signer<'info>,
@@ -18,29 +17,40 @@ pub struct Make<'info> {
     // 2. now here mintA and mintB is token program 
     // interface account tell that it may be 2020 program may be 2022.
     #[account(
-        mint::token_program = token_program
+        mint::token_program = token_program_a
     )]
     pub mint_a : InterfaceAccount<'info , Mint>,
     
+    // leave mint b out to ask for lamports instead of a token
     #[account(
-        mint::token_program = token_program
+        mint::token_program = token_program_b
     )]
-    pub mint_b : InterfaceAccount<'info , Mint>,
+    pub mint_b : Option<InterfaceAccount<'info , Mint>>,
 
     #[account(
         mut,
         associated_token::mint = mint_a,
         associated_token::authority = maker,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_a
     )]
     pub maker_ata_a : InterfaceAccount<'info , TokenAccount>,
 
+    // the escrow seed comes from this counter , so the client never has to pick one
+    #[account(
+        init_if_needed,
+        payer = maker,
+        seeds= [b"profile",maker.key().as_ref()],
+        bump,
+        space = 8 + MakerProfile::INIT_SPACE
+    )]
+    pub maker_profile : Account<'info,MakerProfile>,
+
     #[account(
         init,
         payer = maker,
-        seeds= [b"escrow",maker.key().as_ref(),seeds.to_le_bytes().as_ref()],
+        seeds= [b"escrow",maker.key().as_ref(),maker_profile.escrow_count.to_le_bytes().as_ref()],
         bump,
-        space = 8 + Escrow::InitSpace
+        space = 8 + Escrow::INIT_SPACE
     )]
     pub escrow: Account<'info,Escrow>,
 
@@ -49,25 +59,82 @@ pub struct Make<'info> {
         payer = maker,
         associated_token::mint = mint_a,
         associated_token::authority = escrow,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_a
     )]
     pub vault : InterfaceAccount<'info , TokenAccount>,
 
+    // lists the new escrow in its pair's order book when passed
+    #[account(mut)]
+    pub order_book : Option<Account<'info,OrderBook>>,
+
+    // worst listed escrow of a full book , needed when the new escrow pushes it out
+    #[account(mut)]
+    pub evicted : Option<Account<'info,Escrow>>,
+
     pub associated_token_program : Program<'info,AssociatedToken>,
     pub system_program : Program<'info , System>,
-    // defining a token program 
-    pub token_program : InterfaceAccount<'info , TokenInterfaceAccount>
+    // each side can live under its own token program , so spl token can be swapped for token 2022
+    pub token_program_a : Interface<'info , TokenInterface>,
+    pub token_program_b : Interface<'info , TokenInterface>
 }
 
 impl<'info> Make<'info> {
 
-    pub fn init_escrow(&mut self , seed : u64 , receive_amount : u64 , bumps : &MakeBumps)-> Result<()>{
-        self.escrow.set_inner(Escrow { seed, maker: self.maker.key(), mint_a:self.mint_a.key(), mint_b: self.mint_b.key(), recieve_amount, bump: bumps.escrow });
+    pub fn init_escrow(
+        &mut self ,
+        deposit_amount : u64 ,
+        recieve_amount : u64 ,
+        allowed_taker : Option<Pubkey> ,
+        allowlist_root : Option<[u8; 32]> ,
+        bumps : &MakeBumps
+    )-> Result<()>{
+        require!(deposit_amount > 0 && recieve_amount > 0, ErrorCode::InvalidAmount);
+
+        let seed = self.maker_profile.next_seed(self.maker.key(), bumps.maker_profile)?;
+
+        let (mint_b, payment) = match &self.mint_b {
+            Some(mint_b) => (mint_b.key(), PaymentKind::Token),
+            None => (Pubkey::default(), PaymentKind::Sol),
+        };
+
+        self.escrow.set_inner(Escrow {
+            seed,
+            maker: self.maker.key(),
+            mint_a: self.mint_a.key(),
+            mint_b,
+            payment,
+            recieve_amount,
+            deposit_amount,
+            remaining_amount: deposit_amount,
+            allowed_taker,
+            allowlist_root,
+            expires_at: 0,
+            auction: None,
+            collection: None,
+            indexed: false,
+            bump: bumps.escrow,
+        });
+        Ok(())
+    }
+
+    pub fn set_auction(&mut self , auction : DutchAuction) -> Result<()>{
+        auction.validate()?;
+        self.escrow.recieve_amount = auction.start_price;
+        self.escrow.auction = Some(auction);
         Ok(())
     }
 
-    pub fn deposit(&mut self , deposit_amount : u64 , ) -> Result<()>{
-        let cpi_program = self.token_program.to_account_info();
+    pub fn set_expiry(&mut self , expires_at : i64) -> Result<()>{
+        require!(
+            expires_at == 0 || expires_at > Clock::get()?.unix_timestamp,
+            ErrorCode::InvalidExpiry
+        );
+        self.escrow.expires_at = expires_at;
+        Ok(())
+    }
+
+    pub fn deposit(&mut self , deposit_amount : u64) -> Result<()>{
+        let cpi_program = self.token_program_a.to_account_info();
 
         let transfer_accounts = TransferChecked{
             from : self.maker_ata_a.to_account_info(),
@@ -78,8 +145,36 @@ impl<'info> Make<'info> {
 
         let cpi_ctx = CpiContext::new(cpi_program, transfer_accounts);
 
-        transfer_checked(cpi_ctx, deposit_amount, self.mint_a.decimals)
-        
+        transfer_checked(cpi_ctx, deposit_amount, self.mint_a.decimals)?;
+
+        // a transfer fee mint leaves less than `deposit_amount` in the vault , only offer what actually arrived
+        self.vault.reload()?;
+        self.escrow.deposit_amount = self.vault.amount;
+        self.escrow.remaining_amount = self.vault.amount;
+        Ok(())
+    }
+
+    // a full book of better prices or a deposit under the book's minimum leaves the escrow unlisted ,
+    // it can still be taken directly
+    pub fn list(&mut self) -> Result<()>{
+        let Some(book) = self.order_book.as_deref_mut() else {
+            return Ok(());
+        };
+        book.check_pair(&self.escrow)?;
+
+        let (listed, evicted) = book.insert(OrderBookEntry {
+            escrow: self.escrow.key(),
+            price: self.escrow.unit_price(Clock::get()?.unix_timestamp)?,
+            remaining_amount: self.escrow.remaining_amount,
+        });
+        self.escrow.indexed = listed;
+
+        // the dropped escrow is no longer in the book , so its fills must not need it anymore
+        if let Some(evicted) = evicted {
+            let dropped = self.evicted.as_mut().ok_or(ErrorCode::EvictedEscrowRequired)?;
+            require_keys_eq!(dropped.key(), evicted, ErrorCode::EvictedEscrowRequired);
+            dropped.indexed = false;
+        }
+        Ok(())
     }
 }
-
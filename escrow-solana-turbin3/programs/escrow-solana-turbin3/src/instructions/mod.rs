@@ -1,8 +1,29 @@
 pub mod make; 
-pub use maker::*;
+pub use make::*;
 
 pub mod take;
 pub use take::*;
 
 pub mod refund;
-pub use refund::*;
\ No newline at end of file
+pub use refund::*;
+
+pub mod amend;
+pub use amend::*;
+
+pub mod basket;
+pub use basket::*;
+
+pub mod take_many;
+pub use take_many::*;
+
+pub mod collection;
+pub use collection::*;
+
+pub mod take_sol;
+pub use take_sol::*;
+
+pub mod config;
+pub use config::*;
+
+pub mod order_book;
+pub use order_book::*;
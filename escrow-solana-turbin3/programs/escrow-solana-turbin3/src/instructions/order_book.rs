@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+use crate::error::ErrorCode;
+use crate::state::{FeeConfig, OrderBook, MAX_ORDER_BOOK_ENTRIES};
+
+// the config admin opens the book of a pair , makers then list their escrows by passing it to `make`
+#[derive(Accounts)]
+pub struct InitOrderBook<'info> {
+    #[account(mut)]
+    pub admin : Signer<'info>,
+
+    #[account(
+        has_one = admin @ ErrorCode::Unauthorized,
+        seeds= [b"config"],
+        bump = config.bump,
+    )]
+    pub config : Account<'info,FeeConfig>,
+
+    pub mint_a : InterfaceAccount<'info , Mint>,
+
+    /// CHECK: mint b of the pair , or the system program (the default key) for orders paid in sol
+    pub mint_b : UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds= [b"orderbook",mint_a.key().as_ref(),mint_b.key().as_ref()],
+        bump,
+        space = 8 + OrderBook::INIT_SPACE
+    )]
+    pub order_book : Account<'info,OrderBook>,
+
+    pub system_program : Program<'info , System>
+}
+
+impl<'info> InitOrderBook<'info> {
+    pub fn init_order_book(&mut self , min_amount : u64 , bumps : &InitOrderBookBumps) -> Result<()> {
+        self.order_book.set_inner(OrderBook {
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            min_amount,
+            entries: Vec::with_capacity(MAX_ORDER_BOOK_ENTRIES),
+            bump: bumps.order_book,
+        });
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
-use anchor_lang::{accounts::program, prelude::*};
-use anchor_spl::{associated_token::AssociatedToken, token::CloseAccount, token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked}
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked}
 };
-use crate::state::Escrow;
+use crate::state::{sync_order_book, Escrow, OrderBook};
 
 #[derive(Accounts)]
 pub struct Refund<'info>{
@@ -26,7 +26,7 @@ pub struct Refund<'info>{
         close = maker , 
         has_one = mint_a,
         has_one = maker,
-        seeds= [b"escrow",maker.key().as_ref(),seeds.to_le_bytes().as_ref()],
+        seeds= [b"escrow",maker.key().as_ref(),escrow.seed.to_le_bytes().as_ref()],
         bump = escrow.bump,
     )]
     pub escrow : Account<'info,Escrow>,
@@ -39,20 +39,31 @@ pub struct Refund<'info>{
     )]
     pub vault : InterfaceAccount<'info , TokenAccount>,
 
+    // the pair's order book , required once the escrow is listed in it
+    #[account(mut)]
+    pub order_book : Option<Account<'info,OrderBook>>,
+
     pub associated_token_program : Program<'info,AssociatedToken>,
     pub system_program : Program<'info , System>,
-    pub token_program : InterfaceAccount<'info , TokenInterfaceAccount>
+    pub token_program : Interface<'info , TokenInterface>
 }
 
 impl<'info> Refund<'info>{
-    pub fn refund_to_makerATA_and_close_vault(&mut self){
+    // a refunded escrow has nothing left to offer , so syncing drops it from the book
+    pub fn delist(&mut self) -> Result<()>{
+        self.escrow.remaining_amount = 0;
+        let key = self.escrow.key();
+        sync_order_book(self.order_book.as_deref_mut(), key, &mut self.escrow, Clock::get()?.unix_timestamp)
+    }
+
+    pub fn refund_to_maker_ata_and_close_vault(&mut self) -> Result<()>{
 
         let cpi_program = self.token_program.to_account_info();
         
         let signer_seeds :[&[&[u8]];1]=
         [&[
             b"escrow",
-            self.maker.to_account_info().key().as_ref(),
+            self.maker.key.as_ref(),
             &self.escrow.seed.to_le_bytes()[..],
             &[self.escrow.bump]
         ]];
@@ -60,8 +71,8 @@ impl<'info> Refund<'info>{
         let tranfer_accounts = TransferChecked{
             from:self.vault.to_account_info(),
             to:self.maker_ata_a.to_account_info(),
-            mint:self.mint_a,
-            authority:self.escrow
+            mint:self.mint_a.to_account_info(),
+            authority:self.escrow.to_account_info()
         };
 
         let cpi_ctx = CpiContext::new_with_signer(cpi_program, tranfer_accounts, &signer_seeds);
@@ -71,14 +82,14 @@ impl<'info> Refund<'info>{
         //hence done that.
         // after that we have run the transaction instruction , we need to close the vault.
         
-        let close_account = CloseAccount{
+        let close_accounts = CloseAccount{
             account : self.vault.to_account_info(),
             destination:self.maker.to_account_info(),
             authority:self.escrow.to_account_info(),
         };
 
-        let close_cpi_ctx = CpiContext::new_with_signer(cpi_program , close_account, &signer_seeds);
+        let close_cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), close_accounts, &signer_seeds);
 
-        close_account(close_cpi_ctx);
+        close_account(close_cpi_ctx)
     }
 }
\ No newline at end of file
@@ -1,78 +1,230 @@
-use anchor_lang::{prelude::*, Bump};
+use anchor_lang::prelude::*;
 use anchor_spl::{
-    associated_token::AssociatedToken, token::Mint, token_interface::{
-        transfer_checked, TokenAccount, TokenInterface, TransferChecked
+    associated_token::AssociatedToken, token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked
     }
 };
-use crate::state::Escrow;
+use crate::error::ErrorCode;
+use crate::state::{sync_order_book, Escrow, FeeConfig, OrderBook};
+use crate::utils::amount_with_transfer_fee;
 
-#[derive(account)]
-pub struct Take {
+#[derive(Accounts)]
+pub struct Take<'info> {
     #[account(mut)]
     pub taker : Signer<'info>,
 
+    // maker only receives token b and the rent of the escrow and vault
+    #[account(mut)]
+    pub maker : SystemAccount<'info>,
+
     #[account(
-        mint::token_program
+        mint::token_program = token_program_a
     )]
     pub mint_a : InterfaceAccount<'info , Mint>,
     
     #[account(
-        mint::token_program
+        mint::token_program = token_program_b
     )]
     pub mint_b : InterfaceAccount<'info , Mint>,
     
+    // taker pays token b out of this ATA
     #[account(
         mut , 
-        payer = taker,
         associated_token::mint = mint_b,
-        associated_token::token_program = token_program,
+        associated_token::token_program = token_program_b,
         associated_token::authority  = taker
     )]
-    
     pub taker_ata_b : InterfaceAccount<'info, TokenAccount>, 
     
+    // taker receives the token a held in the vault here
     #[account(
         init_if_needed,
+        payer = taker,
         associated_token::mint = mint_a,
-        associated_token::token_program = token_program,
-        associated_token::authority  = maker
+        associated_token::token_program = token_program_a,
+        associated_token::authority  = taker
     )]
     pub taker_ata_a : InterfaceAccount<'info, TokenAccount>,
-    
+
+    // maker may not hold token b yet, so the taker creates the ATA if needed
     #[account(
-        close = maker,
+        init_if_needed,
+        payer = taker,
         associated_token::mint = mint_b,
+        associated_token::token_program = token_program_b,
+        associated_token::authority  = maker
+    )]
+    pub maker_ata_b : InterfaceAccount<'info, TokenAccount>,
+
+    // only closed once the last token a is filled, see `close_if_filled`
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_a,
+        has_one = mint_b,
+        seeds= [b"escrow",maker.key().as_ref(),escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow : Account<'info,Escrow>,
+    
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
         associated_token::authority = escrow,
-        associated_token::token_program = token_program
-        
+        associated_token::token_program = token_program_a
     )]
     pub vault : InterfaceAccount<'info , TokenAccount>,
 
-    pub assosiated_token_program : Program<'info , AssociatedToken>,
+    /// CHECK: fee config pda , read with `FeeConfig::load` , takes charge no fee until `init_config` created it
+    #[account(
+        seeds= [b"config"],
+        bump,
+    )]
+    pub config : UncheckedAccount<'info>,
+
+    // token account of `config.treasury` for mint b , only needed while the config charges a fee ,
+    // the treasury opens it itself so takers never pay its rent
+    #[account(
+        mut,
+        token::mint = mint_b,
+        token::token_program = token_program_b
+    )]
+    pub treasury_ata_b : Option<InterfaceAccount<'info, TokenAccount>>,
+
+    // frontend that listed the order , gets `config.referrer_share_bps` of the fee
+    #[account(
+        mut,
+        token::mint = mint_b,
+        token::token_program = token_program_b,
+        constraint = referrer_ata_b.owner != taker.key() @ ErrorCode::SelfReferral
+    )]
+    pub referrer_ata_b : Option<InterfaceAccount<'info, TokenAccount>>,
+
+    // the pair's order book , required once the escrow is listed in it
+    #[account(mut)]
+    pub order_book : Option<Account<'info,OrderBook>>,
+
+    pub associated_token_program : Program<'info , AssociatedToken>,
     pub system_program : Program<'info , System>,
-    pub token_program : InterfaceAccount<'info , TokenInterfaceAccount>
+    pub token_program_a : Interface<'info , TokenInterface>,
+    pub token_program_b : Interface<'info , TokenInterface>
 
 }
 
 impl<'info> Take<'info>{
-    pub fn deposit(&mut self , deposit_amount : u64 )-> Result<()>{
+    // taker sends token b for `amount` of token a to the maker
+    pub fn deposit(&mut self , amount : u64 , max_pay : u64 , proof : &[[u8; 32]])-> Result<()>{
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(!self.escrow.is_expired(now), ErrorCode::EscrowExpired);
+        self.escrow.check_taker(self.taker.key, proof)?;
+
+        let price = self.escrow.quote(amount, now)?;
+        // the protocol fee is part of the price , the maker gets the rest
+        let config = FeeConfig::load(&self.config)?;
+        let (treasury_fee, referrer_fee) = config.split_fee(price, self.referrer_ata_b.is_some())?;
+        let maker_part = price - treasury_fee - referrer_fee;
+        // the taker covers any token b transfer fee so the maker's part arrives in full
+        let gross = amount_with_transfer_fee(&self.mint_b, maker_part)?;
+        let total = gross
+            .checked_add(treasury_fee + referrer_fee)
+            .ok_or(ErrorCode::MathOverflow)?;
+        // auction prices move between quote and execution , never charge more than the taker agreed to
+        require!(total <= max_pay, ErrorCode::SlippageExceeded);
+
+        if treasury_fee > 0 {
+            let treasury_ata_b = self.treasury_ata_b.as_ref().ok_or(ErrorCode::TreasuryMismatch)?;
+            require_keys_eq!(treasury_ata_b.owner, config.treasury, ErrorCode::TreasuryMismatch);
+            self.pay_fee(treasury_ata_b.to_account_info(), treasury_fee)?;
+        }
+        if let Some(referrer_ata_b) = &self.referrer_ata_b {
+            self.pay_fee(referrer_ata_b.to_account_info(), referrer_fee)?;
+        }
+
+        let tranfer_accounts = TransferChecked{
+            from:self.taker_ata_b.to_account_info(),
+            mint:self.mint_b.to_account_info(),
+            to:self.maker_ata_b.to_account_info(),
+            authority:self.taker.to_account_info()
+        };
+
+        // the taker signs this one himself, so no signer seeds are needed here
+        let cpi_ctx = CpiContext::new(self.token_program_b.to_account_info(), tranfer_accounts);
 
+        transfer_checked(cpi_ctx, gross, self.mint_b.decimals)
+    }
+
+    fn pay_fee(&self , to : AccountInfo<'info> , fee : u64) -> Result<()>{
+        if fee == 0 {
+            return Ok(());
+        }
+        let fee_accounts = TransferChecked{
+            from:self.taker_ata_b.to_account_info(),
+            mint:self.mint_b.to_account_info(),
+            to,
+            authority:self.taker.to_account_info()
+        };
+        let cpi_ctx = CpiContext::new(self.token_program_b.to_account_info(), fee_accounts);
+        transfer_checked(cpi_ctx, fee, self.mint_b.decimals)
+    }
+
+    // vault pays out `amount` of token a to the taker
+    pub fn withdraw(&mut self , amount : u64) -> Result<()>{
+
+        self.escrow.fill(amount)?;
+
+        // the vault is owned by the escrow pda , so the escrow has to sign
         let signer_seeds :[&[&[u8]];1]=
         [&[
             b"escrow",
-            self.taker.to_account_info().key().as_ref(),
+            self.maker.key.as_ref(),
             &self.escrow.seed.to_le_bytes()[..],
             &[self.escrow.bump]
         ]];
 
         let tranfer_accounts = TransferChecked{
-            from:self.taker_ata_b.to_account_info(),
-            mint:self.mint_b,
+            from:self.vault.to_account_info(),
+            mint:self.mint_a.to_account_info(),
             to:self.taker_ata_a.to_account_info(),
-            authority:self.taker.to_account_info()
+            authority:self.escrow.to_account_info()
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program_a.to_account_info(), tranfer_accounts, &signer_seeds);
+
+        transfer_checked(cpi_ctx, amount, self.mint_a.decimals)
+    }
+
+    // re-lists the escrow with its new price and remaining amount , or drops it once filled
+    pub fn update_order_book(&mut self) -> Result<()>{
+        let key = self.escrow.key();
+        sync_order_book(self.order_book.as_deref_mut(), key, &mut self.escrow, Clock::get()?.unix_timestamp)
+    }
+
+    // once the order is fully filled the vault and the escrow go back to the maker
+    pub fn close_if_filled(&mut self) -> Result<()>{
+
+        if self.escrow.remaining_amount > 0 {
+            return Ok(());
+        }
+
+        let signer_seeds :[&[&[u8]];1]=
+        [&[
+            b"escrow",
+            self.maker.key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump]
+        ]];
+
+        let close_accounts = CloseAccount{
+            account : self.vault.to_account_info(),
+            destination:self.maker.to_account_info(),
+            authority:self.escrow.to_account_info(),
         };
 
-        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), tranfer_accounts, &signer_seeds);
+        let close_cpi_ctx = CpiContext::new_with_signer(self.token_program_a.to_account_info(), close_accounts, &signer_seeds);
 
+        close_account(close_cpi_ctx)?;
+
+        self.escrow.close(self.maker.to_account_info())
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,249 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{create_idempotent, AssociatedToken, Create},
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked
+    }
+};
+use crate::error::ErrorCode;
+use crate::state::{sync_order_book, Escrow, FeeConfig, OrderBook};
+use crate::utils::amount_with_transfer_fee;
+
+// every escrow of the route is passed as (escrow , vault , maker ATA b , maker) in `remaining_accounts`,
+// the maker wallet is needed to hand back the rent once an escrow is fully filled
+pub const ROUTE_LEG_ACCOUNTS: usize = 4;
+
+#[derive(Accounts)]
+pub struct TakeMany<'info> {
+    #[account(mut)]
+    pub taker : Signer<'info>,
+
+    #[account(
+        mint::token_program = token_program_a
+    )]
+    pub mint_a : InterfaceAccount<'info , Mint>,
+
+    #[account(
+        mint::token_program = token_program_b
+    )]
+    pub mint_b : InterfaceAccount<'info , Mint>,
+
+    #[account(
+        mut ,
+        associated_token::mint = mint_b,
+        associated_token::token_program = token_program_b,
+        associated_token::authority  = taker
+    )]
+    pub taker_ata_b : InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_a,
+        associated_token::token_program = token_program_a,
+        associated_token::authority  = taker
+    )]
+    pub taker_ata_a : InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: fee config pda , read with `FeeConfig::load` , takes charge no fee until `init_config` created it
+    #[account(
+        seeds= [b"config"],
+        bump,
+    )]
+    pub config : UncheckedAccount<'info>,
+
+    // token account of `config.treasury` for mint b , only needed while the config charges a fee
+    #[account(
+        mut,
+        token::mint = mint_b,
+        token::token_program = token_program_b
+    )]
+    pub treasury_ata_b : Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint_b,
+        token::token_program = token_program_b,
+        constraint = referrer_ata_b.owner != taker.key() @ ErrorCode::SelfReferral
+    )]
+    pub referrer_ata_b : Option<InterfaceAccount<'info, TokenAccount>>,
+
+    // the pair's order book , required as soon as one escrow of the route is listed in it
+    #[account(mut)]
+    pub order_book : Option<Account<'info,OrderBook>>,
+
+    pub associated_token_program : Program<'info , AssociatedToken>,
+    pub system_program : Program<'info , System>,
+    pub token_program_a : Interface<'info , TokenInterface>,
+    pub token_program_b : Interface<'info , TokenInterface>
+}
+
+impl<'info> TakeMany<'info> {
+    // fills the escrows in the given order until `target_amount` of token a is bought,
+    // reverts if the route runs dry or the token b paid would go above `max_total_pay`
+    pub fn take_many(
+        &mut self ,
+        remaining : &'info [AccountInfo<'info>] ,
+        target_amount : u64 ,
+        max_total_pay : u64
+    ) -> Result<()> {
+        require!(target_amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            !remaining.is_empty() && remaining.chunks_exact(ROUTE_LEG_ACCOUNTS).remainder().is_empty(),
+            ErrorCode::RouteAccountMismatch
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let config = FeeConfig::load(&self.config)?;
+        let with_referrer = self.referrer_ata_b.is_some();
+        let mut filled : u64 = 0;
+        let mut paid : u64 = 0;
+        let mut treasury_total : u64 = 0;
+        let mut referrer_total : u64 = 0;
+
+        for leg in remaining.chunks(ROUTE_LEG_ACCOUNTS) {
+            if filled == target_amount {
+                break;
+            }
+
+            let mut escrow = self.load_escrow(leg)?;
+            require!(!escrow.is_expired(now), ErrorCode::EscrowExpired);
+            escrow.check_taker(self.taker.key, &[])?;
+
+            let amount = escrow.remaining_amount.min(target_amount - filled);
+            let price = escrow.quote(amount, now)?;
+            // the protocol fee is part of each leg's price , its maker gets the rest
+            let (treasury_fee, referrer_fee) = config.split_fee(price, with_referrer)?;
+            let gross = amount_with_transfer_fee(&self.mint_b, price - treasury_fee - referrer_fee)?;
+
+            treasury_total += treasury_fee;
+            referrer_total += referrer_fee;
+            paid = gross
+                .checked_add(treasury_fee + referrer_fee)
+                .and_then(|leg_pay| paid.checked_add(leg_pay))
+                .ok_or(ErrorCode::MathOverflow)?;
+            require!(paid <= max_total_pay, ErrorCode::SlippageExceeded);
+            filled += amount;
+
+            self.settle_leg(leg, &mut escrow, amount, gross, now)?;
+        }
+
+        require!(filled == target_amount, ErrorCode::InsufficientLiquidity);
+
+        // the fees of all legs go out in one transfer each
+        if treasury_total > 0 {
+            let treasury_ata_b = self.treasury_ata_b.as_ref().ok_or(ErrorCode::TreasuryMismatch)?;
+            require_keys_eq!(treasury_ata_b.owner, config.treasury, ErrorCode::TreasuryMismatch);
+            self.pay_fee(treasury_ata_b.to_account_info(), treasury_total)?;
+        }
+        if let Some(referrer_ata_b) = &self.referrer_ata_b {
+            self.pay_fee(referrer_ata_b.to_account_info(), referrer_total)?;
+        }
+        Ok(())
+    }
+
+    fn pay_fee(&self , to : AccountInfo<'info> , fee : u64) -> Result<()> {
+        if fee == 0 {
+            return Ok(());
+        }
+        let fee_accounts = TransferChecked{
+            from:self.taker_ata_b.to_account_info(),
+            mint:self.mint_b.to_account_info(),
+            to,
+            authority:self.taker.to_account_info()
+        };
+        let cpi_ctx = CpiContext::new(self.token_program_b.to_account_info(), fee_accounts);
+        transfer_checked(cpi_ctx, fee, self.mint_b.decimals)
+    }
+
+    fn load_escrow(&self , leg : &'info [AccountInfo<'info>]) -> Result<Account<'info, Escrow>> {
+        let escrow = Account::<Escrow>::try_from(&leg[0])?;
+        require_keys_eq!(escrow.mint_a, self.mint_a.key(), ErrorCode::RouteAccountMismatch);
+        require_keys_eq!(escrow.mint_b, self.mint_b.key(), ErrorCode::RouteAccountMismatch);
+        require_keys_eq!(escrow.maker, leg[3].key(), ErrorCode::RouteAccountMismatch);
+
+        // same check `seeds` + `bump = escrow.bump` does in `Take`
+        let expected = Pubkey::create_program_address(
+            &[b"escrow", escrow.maker.as_ref(), &escrow.seed.to_le_bytes()[..], &[escrow.bump]],
+            &crate::ID,
+        )
+        .map_err(|_| error!(ErrorCode::RouteAccountMismatch))?;
+        require_keys_eq!(expected, escrow.key(), ErrorCode::RouteAccountMismatch);
+
+        let vault = InterfaceAccount::<TokenAccount>::try_from(&leg[1])?;
+        require_keys_eq!(vault.owner, escrow.key(), ErrorCode::RouteAccountMismatch);
+        require_keys_eq!(vault.mint, escrow.mint_a, ErrorCode::RouteAccountMismatch);
+
+        Ok(escrow)
+    }
+
+    fn settle_leg(
+        &mut self ,
+        leg : &'info [AccountInfo<'info>] ,
+        escrow : &mut Account<'info, Escrow> ,
+        amount : u64 ,
+        gross : u64 ,
+        now : i64
+    ) -> Result<()> {
+        let vault = &leg[1];
+        let maker_ata_b = &leg[2];
+        let maker = &leg[3];
+
+        // maker may not hold token b yet , the ATA program rejects anything but the maker's ATA
+        create_idempotent(CpiContext::new(
+            self.associated_token_program.to_account_info(),
+            Create {
+                payer: self.taker.to_account_info(),
+                associated_token: maker_ata_b.clone(),
+                authority: maker.clone(),
+                mint: self.mint_b.to_account_info(),
+                system_program: self.system_program.to_account_info(),
+                token_program: self.token_program_b.to_account_info(),
+            },
+        ))?;
+
+        let pay_accounts = TransferChecked{
+            from:self.taker_ata_b.to_account_info(),
+            mint:self.mint_b.to_account_info(),
+            to:maker_ata_b.clone(),
+            authority:self.taker.to_account_info()
+        };
+        let cpi_ctx = CpiContext::new(self.token_program_b.to_account_info(), pay_accounts);
+        transfer_checked(cpi_ctx, gross, self.mint_b.decimals)?;
+
+        let done = escrow.fill(amount)?;
+        sync_order_book(self.order_book.as_deref_mut(), escrow.key(), escrow, now)?;
+
+        let signer_seeds :[&[&[u8]];1]=
+        [&[
+            b"escrow",
+            escrow.maker.as_ref(),
+            &escrow.seed.to_le_bytes()[..],
+            &[escrow.bump]
+        ]];
+
+        let withdraw_accounts = TransferChecked{
+            from:vault.clone(),
+            mint:self.mint_a.to_account_info(),
+            to:self.taker_ata_a.to_account_info(),
+            authority:escrow.to_account_info()
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program_a.to_account_info(), withdraw_accounts, &signer_seeds);
+        transfer_checked(cpi_ctx, amount, self.mint_a.decimals)?;
+
+        if !done {
+            // accounts loaded from `remaining_accounts` are not written back by anchor
+            return escrow.exit(&crate::ID);
+        }
+
+        let close_accounts = CloseAccount{
+            account : vault.clone(),
+            destination:maker.clone(),
+            authority:escrow.to_account_info(),
+        };
+        let close_cpi_ctx = CpiContext::new_with_signer(self.token_program_a.to_account_info(), close_accounts, &signer_seeds);
+        close_account(close_cpi_ctx)?;
+
+        escrow.close(maker.clone())
+    }
+}
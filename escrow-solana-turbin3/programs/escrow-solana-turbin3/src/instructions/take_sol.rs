@@ -0,0 +1,183 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer};
+use anchor_spl::{
+    associated_token::AssociatedToken, token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked
+    }
+};
+use crate::error::ErrorCode;
+use crate::state::{sync_order_book, Escrow, FeeConfig, OrderBook, PaymentKind};
+
+#[derive(Accounts)]
+pub struct TakeSol<'info> {
+    #[account(mut)]
+    pub taker : Signer<'info>,
+
+    // maker receives the lamports and the rent of the escrow and vault
+    #[account(mut)]
+    pub maker : SystemAccount<'info>,
+
+    #[account(
+        mint::token_program = token_program_a
+    )]
+    pub mint_a : InterfaceAccount<'info , Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_a,
+        associated_token::token_program = token_program_a,
+        associated_token::authority  = taker
+    )]
+    pub taker_ata_a : InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_a,
+        constraint = escrow.payment == PaymentKind::Sol @ ErrorCode::NotSolOrder,
+        seeds= [b"escrow",maker.key().as_ref(),escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow : Account<'info,Escrow>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program_a
+    )]
+    pub vault : InterfaceAccount<'info , TokenAccount>,
+
+    /// CHECK: fee config pda , read with `FeeConfig::load` , takes charge no fee until `init_config` created it
+    #[account(
+        seeds= [b"config"],
+        bump,
+    )]
+    pub config : UncheckedAccount<'info>,
+
+    // `config.treasury` , only needed while the config charges a fee
+    #[account(mut)]
+    pub treasury : Option<SystemAccount<'info>>,
+
+    #[account(
+        mut,
+        constraint = referrer.key() != taker.key() @ ErrorCode::SelfReferral
+    )]
+    pub referrer : Option<SystemAccount<'info>>,
+
+    // the pair's order book , required once the escrow is listed in it
+    #[account(mut)]
+    pub order_book : Option<Account<'info,OrderBook>>,
+
+    pub associated_token_program : Program<'info , AssociatedToken>,
+    pub system_program : Program<'info , System>,
+    pub token_program_a : Interface<'info , TokenInterface>
+}
+
+impl<'info> TakeSol<'info>{
+    // taker sends the lamports for `amount` of token a straight to the maker
+    pub fn deposit(&mut self , amount : u64 , max_pay : u64 , proof : &[[u8; 32]])-> Result<()>{
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(!self.escrow.is_expired(now), ErrorCode::EscrowExpired);
+        self.escrow.check_taker(self.taker.key, proof)?;
+
+        let price = self.escrow.quote(amount, now)?;
+        // the protocol fee is part of the price , the maker gets the rest
+        let config = FeeConfig::load(&self.config)?;
+        let (mut treasury_fee, mut referrer_fee) = config.split_fee(price, self.referrer.is_some())?;
+        if let Some(referrer) = &self.referrer {
+            // a share too small to make an unfunded referrer wallet rent exempt would fail the whole take ,
+            // it goes to the treasury instead
+            let credited = referrer.lamports().saturating_add(referrer_fee);
+            if referrer_fee > 0 && !Rent::get()?.is_exempt(credited, referrer.data_len()) {
+                treasury_fee += referrer_fee;
+                referrer_fee = 0;
+            }
+        }
+        require!(price <= max_pay, ErrorCode::SlippageExceeded);
+
+        self.pay_lamports(self.maker.to_account_info(), price - treasury_fee - referrer_fee)?;
+        if treasury_fee > 0 {
+            let treasury = self.treasury.as_ref().ok_or(ErrorCode::TreasuryMismatch)?;
+            require_keys_eq!(treasury.key(), config.treasury, ErrorCode::TreasuryMismatch);
+            self.pay_lamports(treasury.to_account_info(), treasury_fee)?;
+        }
+        if let Some(referrer) = &self.referrer {
+            self.pay_lamports(referrer.to_account_info(), referrer_fee)?;
+        }
+        Ok(())
+    }
+
+    fn pay_lamports(&self , to : AccountInfo<'info> , lamports : u64) -> Result<()>{
+        if lamports == 0 {
+            return Ok(());
+        }
+        let transfer_accounts = Transfer{
+            from:self.taker.to_account_info(),
+            to
+        };
+        let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), transfer_accounts);
+
+        transfer(cpi_ctx, lamports)
+    }
+
+    // vault pays out `amount` of token a to the taker
+    pub fn withdraw(&mut self , amount : u64) -> Result<()>{
+
+        self.escrow.fill(amount)?;
+
+        let signer_seeds :[&[&[u8]];1]=
+        [&[
+            b"escrow",
+            self.maker.key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump]
+        ]];
+
+        let tranfer_accounts = TransferChecked{
+            from:self.vault.to_account_info(),
+            mint:self.mint_a.to_account_info(),
+            to:self.taker_ata_a.to_account_info(),
+            authority:self.escrow.to_account_info()
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program_a.to_account_info(), tranfer_accounts, &signer_seeds);
+
+        transfer_checked(cpi_ctx, amount, self.mint_a.decimals)
+    }
+
+    // re-lists the escrow with its new price and remaining amount , or drops it once filled
+    pub fn update_order_book(&mut self) -> Result<()>{
+        let key = self.escrow.key();
+        sync_order_book(self.order_book.as_deref_mut(), key, &mut self.escrow, Clock::get()?.unix_timestamp)
+    }
+
+    pub fn close_if_filled(&mut self) -> Result<()>{
+
+        if self.escrow.remaining_amount > 0 {
+            return Ok(());
+        }
+
+        let signer_seeds :[&[&[u8]];1]=
+        [&[
+            b"escrow",
+            self.maker.key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump]
+        ]];
+
+        let close_accounts = CloseAccount{
+            account : self.vault.to_account_info(),
+            destination:self.maker.to_account_info(),
+            authority:self.escrow.to_account_info(),
+        };
+
+        let close_cpi_ctx = CpiContext::new_with_signer(self.token_program_a.to_account_info(), close_accounts, &signer_seeds);
+
+        close_account(close_cpi_ctx)?;
+
+        self.escrow.close(self.maker.to_account_info())
+    }
+}
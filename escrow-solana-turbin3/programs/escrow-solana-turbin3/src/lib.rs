@@ -1,10 +1,12 @@
 use anchor_lang::prelude::*;
 
+pub mod error;
 pub mod instructions;
 pub mod state;
+pub mod utils;
 
-pub use instruction;
-pub use state;
+pub use instructions::*;
+pub use state::*;
 
 
 
@@ -14,15 +16,123 @@ declare_id!("AfxA7zdgcUoyNb2McwehJWdoYChMsjZxMjH44yzoqyai");
 pub mod escrow_solana_turbin3 {
     use super::*;
 
-    pub fn make(ctx: Context<Make>, seed : u64 , deposit_amout : u64 , receive_amout : u64) -> Result<()> {
-        
-        Ok(())
+    // `allowed_taker` and `allowlist_root` turn the order into a private one, leave both empty for a public order
+    // `expires_at` of 0 keeps the order open until it is filled or refunded
+    // the escrow seed is the maker's `MakerProfile::escrow_count`
+    // without a `mint_b` account `recieve_amount` is in lamports and the order is taken with `take_sol`
+    pub fn make(
+        ctx: Context<Make>,
+        deposit_amount : u64 ,
+        recieve_amount : u64 ,
+        allowed_taker : Option<Pubkey> ,
+        allowlist_root : Option<[u8; 32]> ,
+        expires_at : i64
+    ) -> Result<()> {
+        ctx.accounts.init_escrow(deposit_amount, recieve_amount, allowed_taker, allowlist_root, &ctx.bumps)?;
+        ctx.accounts.set_expiry(expires_at)?;
+        ctx.accounts.deposit(deposit_amount)?;
+        ctx.accounts.list()
+    }
+
+    // same as `make` but the price of the whole deposit decays from `auction.start_price` to `auction.end_price`
+    pub fn make_auction(ctx: Context<Make>, deposit_amount : u64 , auction : DutchAuction) -> Result<()> {
+        ctx.accounts.init_escrow(deposit_amount, auction.start_price, None, None, &ctx.bumps)?;
+        ctx.accounts.set_auction(auction)?;
+        ctx.accounts.deposit(deposit_amount)?;
+        ctx.accounts.list()
+    }
+
+    // `amount` is how much token a the taker wants out of the escrow, it can be less than what is left
+    // `max_pay` caps the token b sent , the protocol fee is taken out of the price , `proof` is only needed for orders restricted by an allowlist root
+    pub fn take(ctx: Context<Take>, amount : u64 , max_pay : u64 , proof : Vec<[u8; 32]>) -> Result<()> {
+        ctx.accounts.deposit(amount, max_pay, &proof)?;
+        ctx.accounts.withdraw(amount)?;
+        ctx.accounts.update_order_book()?;
+        ctx.accounts.close_if_filled()
+    }
+
+    // same as `take` for orders made without a mint b , the taker pays `recieve_amount` in lamports
+    pub fn take_sol(ctx: Context<TakeSol>, amount : u64 , max_pay : u64 , proof : Vec<[u8; 32]>) -> Result<()> {
+        ctx.accounts.deposit(amount, max_pay, &proof)?;
+        ctx.accounts.withdraw(amount)?;
+        ctx.accounts.update_order_book()?;
+        ctx.accounts.close_if_filled()
+    }
+
+    // buys `target_amount` of token a across the escrows in `remaining_accounts`, cheapest first is up to the client
+    pub fn take_many<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TakeMany<'info>>,
+        target_amount : u64 ,
+        max_total_pay : u64
+    ) -> Result<()> {
+        ctx.accounts.take_many(ctx.remaining_accounts, target_amount, max_total_pay)
+    }
+
+    // offers the deposit for any one NFT of `collection` , see instructions/collection.rs
+    pub fn make_collection_offer(
+        ctx: Context<MakeCollectionOffer>,
+        deposit_amount : u64 ,
+        collection : Pubkey ,
+        expires_at : i64
+    ) -> Result<()> {
+        ctx.accounts.make_collection_offer(deposit_amount, collection, expires_at, &ctx.bumps)
+    }
+
+    // `nft_metadata` can be left out for token 2022 NFTs that carry a group member extension
+    pub fn take_collection_offer(ctx: Context<TakeCollectionOffer>) -> Result<()> {
+        ctx.accounts.take_collection_offer()
+    }
+
+    // lets the maker reprice the order or move its expiry without refunding it
+    pub fn amend(ctx: Context<Amend>, recieve_amount : Option<u64> , expires_at : Option<i64>) -> Result<()> {
+        ctx.accounts.amend(recieve_amount, expires_at)
     }
-}
 
-#[derive(Accounts)]
-pub struct Initialize {}
+    // opens the order book of a pair , pass the system program as `mint_b` for the book of sol orders
+    // escrows offering less than `min_amount` token a are never listed
+    pub fn init_order_book(ctx: Context<InitOrderBook>, min_amount : u64) -> Result<()> {
+        ctx.accounts.init_order_book(min_amount, &ctx.bumps)
+    }
+
+    // fee config is created once by the program's upgrade authority , until then takes charge no fee
+    // the treasury has to hold a token account of every mint b it charges fees in
+    pub fn init_config(ctx: Context<InitConfig>, treasury : Pubkey , fee_bps : u16 , referrer_share_bps : u16) -> Result<()> {
+        ctx.accounts.init_config(treasury, fee_bps, referrer_share_bps, &ctx.bumps)
+    }
+
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        new_admin : Pubkey ,
+        treasury : Pubkey ,
+        fee_bps : u16 ,
+        referrer_share_bps : u16
+    ) -> Result<()> {
+        ctx.accounts.update_config(new_admin, treasury, fee_bps, referrer_share_bps)
+    }
+
+    pub fn refund(ctx: Context<Refund>) -> Result<()> {
+        ctx.accounts.delist()?;
+        ctx.accounts.refund_to_maker_ata_and_close_vault()
+    }
+
+    // basket swaps pass their per mint accounts through `remaining_accounts`, see instructions/basket.rs
+    pub fn make_basket<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MakeBasket<'info>>,
+        offered_amounts : Vec<u64> ,
+        requested : Vec<BasketLeg>
+    ) -> Result<()> {
+        ctx.accounts.make_basket(ctx.remaining_accounts, offered_amounts, requested, &ctx.bumps)
+    }
+
+    pub fn take_basket<'info>(ctx: Context<'_, '_, 'info, 'info, TakeBasket<'info>>) -> Result<()> {
+        ctx.accounts.take_basket(ctx.remaining_accounts)
+    }
+
+    pub fn refund_basket<'info>(ctx: Context<'_, '_, 'info, 'info, RefundBasket<'info>>) -> Result<()> {
+        ctx.accounts.refund_basket(ctx.remaining_accounts)
+    }
+}
 
 // since we are having multiple instruction , hence creating modules for 
 // that
-// So , creating modules names instructions.
\ No newline at end of file
+// So , creating modules names instructions.
@@ -4,6 +4,9 @@
 // because there are numbers of instructions 
 
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+use crate::error::ErrorCode;
 
 // what state we are gonna store ???
 
@@ -13,10 +16,321 @@ use anchor_lang::prelude::*;
 pub struct Escrow {
     pub seed : u64 ,
     pub maker : Pubkey,
+    pub mint_a : Pubkey,
+    pub mint_b : Pubkey,        // left as the default key on collection and sol orders
+    pub payment : PaymentKind,  // what the taker pays with
+    pub recieve_amount : u64,   // token b (or lamports) asked for the whole deposit
+    pub deposit_amount : u64,   // token a deposited at make
+    pub remaining_amount : u64, // token a not filled yet
+    pub allowed_taker : Option<Pubkey>,     // OTC order, only this wallet can take
+    pub allowlist_root : Option<[u8; 32]>,  // merkle root of the wallets that can take
+    pub expires_at : i64,       // unix timestamp after which take is rejected, 0 means never
+    pub auction : Option<DutchAuction>, // when set the price decays instead of using recieve_amount
+    pub collection : Option<Pubkey>,    // when set any one NFT of this collection takes the whole deposit
+    pub indexed : bool,         // listed in the pair's `OrderBook` , every fill then has to pass the book
+    pub bump : u8
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum PaymentKind {
+    // `mint_b` through `take` / `take_many`
+    Token,
+    // native lamports through `take_sol` , no wrapped sol account needed
+    Sol,
+}
+
+// price (token b for the whole deposit) falls linearly from `start_price` to `end_price`
+// between `start_ts` and `end_ts` , and stays at `end_price` afterwards
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct DutchAuction {
+    pub start_price : u64,
+    pub end_price : u64,
+    pub start_ts : i64,
+    pub end_ts : i64,
+}
+
+impl DutchAuction {
+    pub fn validate(&self) -> Result<()> {
+        require!(self.end_price > 0 && self.start_price >= self.end_price, ErrorCode::InvalidAuction);
+        require!(self.end_ts > self.start_ts, ErrorCode::InvalidAuction);
+        Ok(())
+    }
+
+    pub fn price_at(&self, now : i64) -> u64 {
+        if now <= self.start_ts {
+            return self.start_price;
+        }
+        if now >= self.end_ts {
+            return self.end_price;
+        }
+        let elapsed = (now - self.start_ts) as u128;
+        let duration = (self.end_ts - self.start_ts) as u128;
+        let drop = (self.start_price - self.end_price) as u128;
+        // the decay is rounded down , so the price is rounded up in the maker's favour
+        self.start_price - (drop * elapsed / duration) as u64
+    }
+}
+
+impl Escrow {
+    // token b asked for the whole deposit right now
+    pub fn current_price(&self, now : i64) -> u64 {
+        match self.auction {
+            Some(auction) => auction.price_at(now),
+            None => self.recieve_amount,
+        }
+    }
+
+    // token b owed for `amount` of token a at the current deposit/price ratio,
+    // rounded up so partial fills never short the maker
+    pub fn quote(&self, amount : u64 , now : i64) -> Result<u64> {
+        let numerator = (amount as u128)
+            .checked_mul(self.current_price(now) as u128)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let price = numerator.div_ceil(self.deposit_amount as u128);
+        u64::try_from(price).map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+
+    pub fn is_expired(&self, now : i64) -> bool {
+        self.expires_at != 0 && now >= self.expires_at
+    }
+
+    // private orders can only be taken by the named taker or a wallet proven to be in the allowlist
+    pub fn check_taker(&self, taker : &Pubkey, proof : &[[u8; 32]]) -> Result<()> {
+        if self.allowed_taker.is_none() && self.allowlist_root.is_none() {
+            return Ok(());
+        }
+        if self.allowed_taker == Some(*taker) {
+            return Ok(());
+        }
+        if let Some(root) = self.allowlist_root {
+            if verify_allowlist(root, taker, proof) {
+                return Ok(());
+            }
+        }
+        err!(ErrorCode::TakerNotAllowed)
+    }
+
+    // token b asked per `PRICE_SCALE` token a , rounded up like `quote`
+    pub fn unit_price(&self, now : i64) -> Result<u64> {
+        let numerator = (self.current_price(now) as u128)
+            .checked_mul(PRICE_SCALE)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let price = numerator.div_ceil(self.deposit_amount as u128);
+        u64::try_from(price).map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+
+    // records a fill of `amount` token a, returns true once nothing is left
+    pub fn fill(&mut self , amount : u64) -> Result<bool> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(amount <= self.remaining_amount, ErrorCode::FillTooLarge);
+        self.remaining_amount -= amount;
+        Ok(self.remaining_amount == 0)
+    }
+}
+
+// leaves are sha256(wallet), every level hashes the sorted pair so proofs don't need a direction
+pub fn verify_allowlist(root : [u8; 32], taker : &Pubkey, proof : &[[u8; 32]]) -> bool {
+    let mut node = hashv(&[taker.as_ref()]).to_bytes();
+    for sibling in proof {
+        node = if node <= *sibling {
+            hashv(&[&node, sibling]).to_bytes()
+        } else {
+            hashv(&[sibling, &node]).to_bytes()
+        };
+    }
+    node == root
+}
+
+// most mints a basket can offer or request , keeps every leg's accounts inside one transaction
+pub const MAX_BASKET_LEGS: usize = 4;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct BasketLeg {
+    pub mint : Pubkey,
+    pub amount : u64,
+}
+
+// several mints offered for several mints , every offered mint sits in its own vault ATA owned by the basket
+#[account]
+#[derive(InitSpace)]
+pub struct Basket {
+    pub seed : u64,
+    pub maker : Pubkey,
+    #[max_len(MAX_BASKET_LEGS)]
+    pub offered : Vec<BasketLeg>,
+    #[max_len(MAX_BASKET_LEGS)]
+    pub requested : Vec<BasketLeg>,
+    pub bump : u8
+}
+
+// one per maker , `escrow_count` is the seed of the maker's next escrow or basket
+// so every order of a maker sits at seeds 0..escrow_count
+#[account]
+#[derive(InitSpace)]
+pub struct MakerProfile {
+    pub maker : Pubkey,
+    pub escrow_count : u64,
+    pub bump : u8
+}
+
+impl MakerProfile {
+    // hands out the next seed , the first call also records who owns the profile
+    pub fn next_seed(&mut self , maker : Pubkey , bump : u8) -> Result<u64> {
+        if self.maker == Pubkey::default() {
+            self.maker = maker;
+            self.bump = bump;
+        }
+        let seed = self.escrow_count;
+        self.escrow_count = seed.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        Ok(seed)
+    }
+}
+
+// most a protocol fee can be , 10%
+pub const MAX_FEE_BPS: u16 = 1_000;
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+// program wide protocol fee , taken out of the price of every `take`, `take_sol` and `take_many`
+#[account]
+#[derive(InitSpace)]
+pub struct FeeConfig {
+    pub admin : Pubkey,
+    pub treasury : Pubkey,          // wallet , token fees go to its ATA of mint b
+    pub fee_bps : u16,
+    pub referrer_share_bps : u16,   // part of the fee handed to the referrer when one is passed
+    pub bump : u8
+}
+
+impl FeeConfig {
+    // reads the config pda , until `init_config` created it there is nothing there and takes charge no fee
+    pub fn load(info : &AccountInfo) -> Result<FeeConfig> {
+        if info.data_is_empty() {
+            return Ok(FeeConfig {
+                admin: Pubkey::default(),
+                treasury: Pubkey::default(),
+                fee_bps: 0,
+                referrer_share_bps: 0,
+                bump: 0,
+            });
+        }
+        require_keys_eq!(*info.owner, crate::ID, ErrorCode::InvalidFeeConfig);
+        FeeConfig::try_deserialize(&mut &info.try_borrow_data()?[..])
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        require!(self.fee_bps <= MAX_FEE_BPS, ErrorCode::InvalidFeeConfig);
+        require!(self.referrer_share_bps as u64 <= BPS_DENOMINATOR, ErrorCode::InvalidFeeConfig);
+        Ok(())
+    }
+
+    // splits the fee on `price` into (treasury part , referrer part) , both rounded down ,
+    // the maker gets what is left of the price
+    pub fn split_fee(&self , price : u64 , with_referrer : bool) -> Result<(u64, u64)> {
+        let fee = (price as u128)
+            .checked_mul(self.fee_bps as u128)
+            .ok_or(ErrorCode::MathOverflow)?
+            / BPS_DENOMINATOR as u128;
+        let rebate = if with_referrer {
+            fee * self.referrer_share_bps as u128 / BPS_DENOMINATOR as u128
+        } else {
+            0
+        };
+        // fee is at most 10% of a u64 , so both parts fit
+        Ok(((fee - rebate) as u64, rebate as u64))
+    }
+}
+
+// most escrows a pair's order book lists , the rest can still be taken but are only found by scanning
+pub const MAX_ORDER_BOOK_ENTRIES: usize = 32;
+// order book prices are token b per 10^9 token a
+pub const PRICE_SCALE: u128 = 1_000_000_000;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct OrderBookEntry {
+    pub escrow : Pubkey,
+    pub price : u64,            // see `Escrow::unit_price` , auctions are listed at their price of the last update
+    pub remaining_amount : u64,
+}
+
+// open escrows of one (mint a , mint b) pair sorted cheapest first , sol orders use the default key as mint b
+// only `make` adds escrows , fills , amends and refunds keep a listed escrow's entry up to date
+#[account]
+#[derive(InitSpace)]
+pub struct OrderBook {
     pub mint_a : Pubkey,
     pub mint_b : Pubkey,
-    pub recieve_amount : u64,
+    pub min_amount : u64,       // least token a an escrow has to offer to be listed , keeps dust out of the book
+    #[max_len(MAX_ORDER_BOOK_ENTRIES)]
+    pub entries : Vec<OrderBookEntry>,
     pub bump : u8
 }
 
+impl OrderBook {
+    pub fn check_pair(&self , escrow : &Escrow) -> Result<()> {
+        require_keys_eq!(self.mint_a, escrow.mint_a, ErrorCode::OrderBookMismatch);
+        require_keys_eq!(self.mint_b, escrow.mint_b, ErrorCode::OrderBookMismatch);
+        require!(escrow.collection.is_none(), ErrorCode::OrderBookMismatch);
+        Ok(())
+    }
+
+    pub fn remove(&mut self , escrow : &Pubkey) {
+        self.entries.retain(|entry| entry.escrow != *escrow);
+    }
+
+    fn place(&self , price : u64) -> usize {
+        self.entries.partition_point(|listed| listed.price <= price)
+    }
+
+    // lists a new escrow at its sorted position , returns whether it got listed and the escrow a full book
+    // dropped to make room , nothing is listed when the new escrow would be the worst price
+    pub fn insert(&mut self , entry : OrderBookEntry) -> (bool, Option<Pubkey>) {
+        if entry.remaining_amount < self.min_amount {
+            return (false, None);
+        }
+        let position = self.place(entry.price);
+        if position >= MAX_ORDER_BOOK_ENTRIES {
+            return (false, None);
+        }
+        let evicted = if self.entries.len() == MAX_ORDER_BOOK_ENTRIES {
+            self.entries.pop().map(|dropped| dropped.escrow)
+        } else {
+            None
+        };
+        self.entries.insert(position, entry);
+        (true, evicted)
+    }
+
+    // moves a listed escrow to its new sorted position , its own slot is freed first so nothing is dropped
+    pub fn update(&mut self , entry : OrderBookEntry) {
+        self.remove(&entry.escrow);
+        let position = self.place(entry.price);
+        self.entries.insert(position, entry);
+    }
+}
+
+// keeps the pair's order book in line with a listed escrow after a fill , amend or refund ,
+// the book can only be skipped for escrows that are not listed in it
+pub fn sync_order_book(order_book : Option<&mut OrderBook>, key : Pubkey, escrow : &mut Escrow, now : i64) -> Result<()> {
+    let Some(book) = order_book else {
+        require!(!escrow.indexed, ErrorCode::OrderBookRequired);
+        return Ok(());
+    };
+    book.check_pair(escrow)?;
+    if !escrow.indexed {
+        return Ok(());
+    }
+
+    // filled escrows and partial fills that left dust behind leave the book
+    if escrow.remaining_amount < book.min_amount.max(1) {
+        book.remove(&key);
+        escrow.indexed = false;
+        return Ok(());
+    }
 
+    book.update(OrderBookEntry {
+        escrow: key,
+        price: escrow.unit_price(now)?,
+        remaining_amount: escrow.remaining_amount,
+    });
+    Ok(())
+}
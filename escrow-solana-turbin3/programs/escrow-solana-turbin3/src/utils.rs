@@ -0,0 +1,113 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use anchor_spl::token_interface::{get_mint_extension_data, Mint};
+use spl_token_group_interface::state::TokenGroupMember;
+
+use crate::error::ErrorCode;
+
+// how much has to be sent so that `net` arrives after the mint's transfer fee,
+// plain spl token mints and token 2022 mints without the extension just return `net`
+pub fn amount_with_transfer_fee(mint : &InterfaceAccount<Mint>, net : u64) -> Result<u64> {
+    let mint_info = mint.to_account_info();
+    let Ok(fee_config) = get_mint_extension_data::<TransferFeeConfig>(&mint_info) else {
+        return Ok(net);
+    };
+
+    let epoch = Clock::get()?.epoch;
+    let fee = fee_config
+        .calculate_inverse_epoch_fee(epoch, net)
+        .ok_or(ErrorCode::MathOverflow)?;
+    net.checked_add(fee).ok_or(error!(ErrorCode::MathOverflow))
+}
+
+// metaplex token metadata program , its metadata account sits at ["metadata", program id, mint]
+pub const METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+// metadata account tag of a v1 metaplex metadata account
+const METADATA_V1_KEY: u8 = 4;
+
+// checks that `nft_mint` is a single NFT belonging to `collection`, either through its token 2022
+// group member extension or through a verified collection in a metaplex style metadata account
+pub fn verify_collection(
+    nft_mint : &InterfaceAccount<Mint>,
+    metadata : Option<&AccountInfo>,
+    collection : &Pubkey
+) -> Result<()> {
+    require!(nft_mint.decimals == 0 && nft_mint.supply == 1, ErrorCode::NotAnNft);
+
+    let mint_info = nft_mint.to_account_info();
+    if let Ok(member) = get_mint_extension_data::<TokenGroupMember>(&mint_info) {
+        require_keys_eq!(member.mint, nft_mint.key(), ErrorCode::CollectionMismatch);
+        require_keys_eq!(member.group, *collection, ErrorCode::CollectionMismatch);
+        return Ok(());
+    }
+
+    let metadata = metadata.ok_or(ErrorCode::CollectionMismatch)?;
+    require_keys_eq!(*metadata.owner, METADATA_PROGRAM_ID, ErrorCode::CollectionMismatch);
+    let (expected, _) = Pubkey::find_program_address(
+        &[b"metadata", METADATA_PROGRAM_ID.as_ref(), nft_mint.key().as_ref()],
+        &METADATA_PROGRAM_ID,
+    );
+    require_keys_eq!(metadata.key(), expected, ErrorCode::CollectionMismatch);
+
+    let data = metadata.try_borrow_data()?;
+    match read_metadata_collection(&data) {
+        Some((mint, true, key)) if mint == nft_mint.key() && key == *collection => Ok(()),
+        _ => err!(ErrorCode::CollectionMismatch),
+    }
+}
+
+// walks the borsh layout of a metaplex metadata account up to its collection field and
+// returns (mint , collection verified , collection key) , fields after the collection are never read
+fn read_metadata_collection(data : &[u8]) -> Option<(Pubkey, bool, Pubkey)> {
+    let mut reader = ByteReader { data, offset: 0 };
+
+    if reader.take(1)?[0] != METADATA_V1_KEY {
+        return None;
+    }
+    reader.take(32)?;                              // update authority
+    let mint = Pubkey::try_from(reader.take(32)?).ok()?;
+    for _ in 0..3 {                                // name , symbol , uri
+        let len = reader.read_u32()? as usize;
+        reader.take(len)?;
+    }
+    reader.take(2)?;                               // seller fee basis points
+    if reader.read_bool()? {                       // creators , 34 bytes each
+        let count = reader.read_u32()? as usize;
+        reader.take(count.checked_mul(34)?)?;
+    }
+    reader.take(2)?;                               // primary sale happened , is mutable
+    for _ in 0..2 {                                // edition nonce , token standard
+        if reader.read_bool()? {
+            reader.take(1)?;
+        }
+    }
+    if !reader.read_bool()? {
+        return None;
+    }
+    let verified = reader.read_bool()?;
+    let key = Pubkey::try_from(reader.take(32)?).ok()?;
+    Some((mint, verified, key))
+}
+
+struct ByteReader<'a> {
+    data : &'a [u8],
+    offset : usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self , len : usize) -> Option<&'a [u8]> {
+        let end = self.offset.checked_add(len)?;
+        let bytes = self.data.get(self.offset..end)?;
+        self.offset = end;
+        Some(bytes)
+    }
+
+    fn read_u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn read_bool(&mut self) -> Option<bool> {
+        Some(self.take(1)?[0] != 0)
+    }
+}
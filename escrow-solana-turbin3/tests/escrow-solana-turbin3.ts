@@ -1,16 +1,1013 @@
 import * as anchor from "@coral-xyz/anchor";
-import { Program } from "@coral-xyz/anchor";
+import { Program, BN } from "@coral-xyz/anchor";
+import {
+  Keypair,
+  PublicKey,
+  LAMPORTS_PER_SOL,
+  SystemProgram,
+  Transaction,
+  sendAndConfirmTransaction,
+} from "@solana/web3.js";
+import {
+  ExtensionType,
+  TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
+  createInitializeGroupMemberPointerInstruction,
+  createInitializeGroupPointerInstruction,
+  createInitializeMintInstruction,
+  createInitializeTransferFeeConfigInstruction,
+  createMint,
+  getMintLen,
+  getAssociatedTokenAddressSync,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+  tokenGroupInitializeGroupWithRentTransfer,
+  tokenGroupMemberInitializeWithRentTransfer,
+} from "@solana/spl-token";
+import { assert } from "chai";
+import { createHash } from "crypto";
+import { readFileSync } from "fs";
 import { EscrowSolanaTurbin3 } from "../target/types/escrow_solana_turbin3";
 
 describe("escrow-solana-turbin3", () => {
   // Configure the client to use the local cluster.
-  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
 
   const program = anchor.workspace.escrowSolanaTurbin3 as Program<EscrowSolanaTurbin3>;
+  const connection = provider.connection;
 
-  it("Is initialized!", async () => {
-    // Add your test here.
-    const tx = await program.methods.initialize().rpc();
-    console.log("Your transaction signature", tx);
+  const maker = Keypair.generate();
+  const taker = Keypair.generate();
+  const treasury = Keypair.generate();
+  const referrer = Keypair.generate();
+
+  let mintA: PublicKey;
+  let mintB: PublicKey;
+  let makerAtaA: PublicKey;
+  let takerAtaB: PublicKey;
+  let treasuryAtaB: PublicKey;
+  let referrerAtaB: PublicKey;
+
+  const config = PublicKey.findProgramAddressSync([Buffer.from("config")], program.programId)[0];
+  const programData = PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+  )[0];
+
+  const escrowFor = (seed: BN) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+
+  const makerProfile = PublicKey.findProgramAddressSync(
+    [Buffer.from("profile"), maker.publicKey.toBuffer()],
+    program.programId
+  )[0];
+
+  // escrows are seeded by the maker's profile counter
+  const nextSeed = async () => {
+    const profile = await program.account.makerProfile.fetchNullable(makerProfile);
+    return profile ? profile.escrowCount : new BN(0);
+  };
+
+  const make = async (
+    deposit: number,
+    receive: number,
+    allowedTaker: PublicKey | null = null,
+    orderBook: PublicKey | null = null,
+    allowlistRoot: Buffer | null = null,
+    expiresAt = 0
+  ) => {
+    const escrow = escrowFor(await nextSeed());
+    await program.methods
+      .make(new BN(deposit), new BN(receive), allowedTaker, allowlistRoot && Array.from(allowlistRoot), new BN(expiresAt))
+      .accountsPartial({
+        maker: maker.publicKey,
+        mintA,
+        mintB,
+        makerAtaA,
+        makerProfile,
+        escrow,
+        vault: getAssociatedTokenAddressSync(mintA, escrow, true),
+        orderBook,
+        tokenProgramA: TOKEN_PROGRAM_ID,
+        tokenProgramB: TOKEN_PROGRAM_ID,
+      })
+      .signers([maker])
+      .rpc();
+    return escrow;
+  };
+
+  const U64_MAX = new BN("18446744073709551615");
+
+  const take = (
+    escrow: PublicKey,
+    amount: number,
+    maxPay: BN = U64_MAX,
+    referrerAta: PublicKey | null = null,
+    orderBook: PublicKey | null = null,
+    proof: Buffer[] = []
+  ) =>
+    program.methods
+      .take(new BN(amount), maxPay, proof.map((node) => Array.from(node)))
+      .accountsPartial({
+        taker: taker.publicKey,
+        maker: maker.publicKey,
+        mintA,
+        mintB,
+        takerAtaB,
+        takerAtaA: getAssociatedTokenAddressSync(mintA, taker.publicKey),
+        makerAtaB: getAssociatedTokenAddressSync(mintB, maker.publicKey),
+        escrow,
+        vault: getAssociatedTokenAddressSync(mintA, escrow, true),
+        config,
+        treasuryAtaB,
+        referrerAtaB: referrerAta,
+        orderBook,
+        tokenProgramA: TOKEN_PROGRAM_ID,
+        tokenProgramB: TOKEN_PROGRAM_ID,
+      })
+      .signers([taker])
+      .rpc();
+
+  const balance = async (mint: PublicKey, owner: PublicKey, tokenProgram = TOKEN_PROGRAM_ID) =>
+    Number(
+      (await connection.getTokenAccountBalance(getAssociatedTokenAddressSync(mint, owner, true, tokenProgram))).value
+        .amount
+    );
+
+  const chainTime = async () => {
+    const slot = await connection.getSlot();
+    return (await connection.getBlockTime(slot)) as number;
+  };
+  const waitUntil = async (timestamp: number) => {
+    while ((await chainTime()) <= timestamp) {
+      await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+  };
+
+  // asserts that `promise` fails with the program error `code`
+  const expectError = async (promise: Promise<unknown>, code: string) => {
+    try {
+      await promise;
+      assert.fail(`expected ${code}`);
+    } catch (err) {
+      assert.include(String(err), code);
+    }
+  };
+
+  // same tree as `verify_allowlist` , leaves are sha256(wallet) and every level hashes the sorted pair ,
+  // an odd node out is carried up to the next level as is
+  const sha256 = (...parts: Buffer[]) => createHash("sha256").update(Buffer.concat(parts)).digest();
+  const hashPair = (a: Buffer, b: Buffer) => (Buffer.compare(a, b) <= 0 ? sha256(a, b) : sha256(b, a));
+
+  const allowlist = (wallets: PublicKey[]) => {
+    const levels = [wallets.map((wallet) => sha256(wallet.toBuffer()))];
+    while (levels[levels.length - 1].length > 1) {
+      const level = levels[levels.length - 1];
+      const next: Buffer[] = [];
+      for (let i = 0; i < level.length; i += 2) {
+        next.push(i + 1 < level.length ? hashPair(level[i], level[i + 1]) : level[i]);
+      }
+      levels.push(next);
+    }
+
+    const proof = (wallet: PublicKey) => {
+      let index = levels[0].findIndex((leaf) => leaf.equals(sha256(wallet.toBuffer())));
+      const nodes: Buffer[] = [];
+      for (const level of levels.slice(0, -1)) {
+        if ((index ^ 1) < level.length) {
+          nodes.push(level[index ^ 1]);
+        }
+        index >>= 1;
+      }
+      return nodes;
+    };
+
+    return { root: levels[levels.length - 1][0], proof };
+  };
+
+  before(async () => {
+    for (const kp of [maker, taker]) {
+      const sig = await connection.requestAirdrop(kp.publicKey, 10 * LAMPORTS_PER_SOL);
+      await connection.confirmTransaction(sig);
+    }
+
+    mintA = await createMint(connection, maker, maker.publicKey, null, 6);
+    mintB = await createMint(connection, taker, taker.publicKey, null, 6);
+
+    makerAtaA = (await getOrCreateAssociatedTokenAccount(connection, maker, mintA, maker.publicKey)).address;
+    takerAtaB = (await getOrCreateAssociatedTokenAccount(connection, taker, mintB, taker.publicKey)).address;
+
+    await mintTo(connection, maker, mintA, makerAtaA, maker, 1_000_000);
+    await mintTo(connection, taker, mintB, takerAtaB, taker, 1_000_000);
+
+    treasuryAtaB = (await getOrCreateAssociatedTokenAccount(connection, taker, mintB, treasury.publicKey)).address;
+    referrerAtaB = (await getOrCreateAssociatedTokenAccount(connection, taker, mintB, referrer.publicKey)).address;
+
+    // 1% fee out of the price , half of it goes to the referrer when one is passed
+    await program.methods
+      .initConfig(treasury.publicKey, 100, 5_000)
+      .accountsPartial({
+        admin: provider.wallet.publicKey,
+        config,
+        programData,
+      })
+      .rpc();
+  });
+
+  it("make & take", async () => {
+    const escrow = await make(500, 300);
+
+    await take(escrow, 500);
+
+    const makerB = await connection.getTokenAccountBalance(getAssociatedTokenAddressSync(mintB, maker.publicKey));
+    const takerA = await connection.getTokenAccountBalance(getAssociatedTokenAddressSync(mintA, taker.publicKey));
+    // 300 less the 1% fee
+    assert.equal(makerB.value.amount, "297");
+    assert.equal(takerA.value.amount, "500");
+    assert.isNull(await connection.getAccountInfo(escrow));
+  });
+
+  it("partial fills round in the maker's favour", async () => {
+    const escrow = await make(1_000, 333);
+    const makerBefore = await balance(mintB, maker.publicKey);
+
+    await take(escrow, 400);
+    const state = await program.account.escrow.fetch(escrow);
+    assert.equal(state.remainingAmount.toNumber(), 600);
+    // 400 * 333 / 1000 = 133.2, rounded up , less the 1% fee rounded down
+    assert.equal((await balance(mintB, maker.publicKey)) - makerBefore, 133);
+
+    await take(escrow, 600);
+    assert.equal((await balance(mintB, maker.publicKey)) - makerBefore, 133 + 198);
+    assert.isNull(await connection.getAccountInfo(escrow));
+  });
+
+  it("fees come out of the price and go to the treasury and the referrer", async () => {
+    const escrow = await make(1_000, 1_000);
+    const makerBefore = await balance(mintB, maker.publicKey);
+    const takerBefore = await balance(mintB, taker.publicKey);
+    const treasuryBefore = await balance(mintB, treasury.publicKey);
+
+    await take(escrow, 1_000, U64_MAX, referrerAtaB);
+
+    assert.equal((await balance(mintB, maker.publicKey)) - makerBefore, 990);
+    assert.equal(takerBefore - (await balance(mintB, taker.publicKey)), 1_000);
+    assert.equal((await balance(mintB, treasury.publicKey)) - treasuryBefore, 5);
+    assert.equal(await balance(mintB, referrer.publicKey), 5);
+  });
+
+  it("order book lists open escrows cheapest first", async () => {
+    const orderBook = PublicKey.findProgramAddressSync(
+      [Buffer.from("orderbook"), mintA.toBuffer(), mintB.toBuffer()],
+      program.programId
+    )[0];
+    await program.methods
+      .initOrderBook(new BN(1))
+      .accountsPartial({ admin: provider.wallet.publicKey, config, mintA, mintB, orderBook })
+      .rpc();
+
+    const pricey = await make(100, 300, null, orderBook);
+    const cheap = await make(100, 100, null, orderBook);
+
+    let book = await program.account.orderBook.fetch(orderBook);
+    assert.deepEqual(book.entries.map((entry) => entry.escrow.toBase58()), [cheap.toBase58(), pricey.toBase58()]);
+    // 100 token b for 100 token a , quoted per 10^9 token a
+    assert.equal(book.entries[0].price.toString(), "1000000000");
+
+    // listed escrows can't be filled without the book
+    try {
+      await take(cheap, 100);
+      assert.fail("take should have been rejected");
+    } catch (err) {
+      assert.include(String(err), "OrderBookRequired");
+    }
+
+    await take(cheap, 40, U64_MAX, null, orderBook);
+    book = await program.account.orderBook.fetch(orderBook);
+    assert.equal(book.entries[0].remainingAmount.toNumber(), 60);
+
+    await take(cheap, 60, U64_MAX, null, orderBook);
+    book = await program.account.orderBook.fetch(orderBook);
+    assert.deepEqual(book.entries.map((entry) => entry.escrow.toBase58()), [pricey.toBase58()]);
+  });
+
+  it("private orders reject other takers", async () => {
+    const escrow = await make(100, 100, Keypair.generate().publicKey);
+    try {
+      await take(escrow, 100);
+      assert.fail("take should have been rejected");
+    } catch (err) {
+      assert.include(String(err), "TakerNotAllowed");
+    }
+  });
+
+  it("take_many routes a fill across escrows", async () => {
+    const first = await make(300, 300);
+    const second = await make(500, 1_000);
+    const makerBefore = await balance(mintB, maker.publicKey);
+
+    const legs = [first, second].flatMap((escrow) => [
+      { pubkey: escrow, isSigner: false, isWritable: true },
+      { pubkey: getAssociatedTokenAddressSync(mintA, escrow, true), isSigner: false, isWritable: true },
+      { pubkey: getAssociatedTokenAddressSync(mintB, maker.publicKey), isSigner: false, isWritable: true },
+      { pubkey: maker.publicKey, isSigner: false, isWritable: true },
+    ]);
+
+    // 300 from the first escrow at 1:1 , then 100 from the second at 2:1 , the 1% fee is part of that
+    await program.methods
+      .takeMany(new BN(400), new BN(500))
+      .accountsPartial({
+        taker: taker.publicKey,
+        mintA,
+        mintB,
+        takerAtaB,
+        takerAtaA: getAssociatedTokenAddressSync(mintA, taker.publicKey),
+        config,
+        treasuryAtaB,
+        referrerAtaB: null,
+        tokenProgramA: TOKEN_PROGRAM_ID,
+        tokenProgramB: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts(legs)
+      .signers([taker])
+      .rpc();
+
+    assert.equal((await balance(mintB, maker.publicKey)) - makerBefore, 297 + 198);
+    assert.isNull(await connection.getAccountInfo(first));
+    const state = await program.account.escrow.fetch(second);
+    assert.equal(state.remainingAmount.toNumber(), 400);
+  });
+
+  it("orders without mint b are paid in lamports", async () => {
+    const escrow = escrowFor(await nextSeed());
+    const vault = getAssociatedTokenAddressSync(mintA, escrow, true);
+    await program.methods
+      .make(new BN(100), new BN(LAMPORTS_PER_SOL), null, null, new BN(0))
+      .accountsPartial({
+        maker: maker.publicKey,
+        mintA,
+        mintB: null,
+        makerAtaA,
+        makerProfile,
+        escrow,
+        vault,
+        tokenProgramA: TOKEN_PROGRAM_ID,
+        tokenProgramB: TOKEN_PROGRAM_ID,
+      })
+      .signers([maker])
+      .rpc();
+    assert.deepEqual((await program.account.escrow.fetch(escrow)).payment, { sol: {} });
+
+    const makerBefore = await connection.getBalance(maker.publicKey);
+    await program.methods
+      .takeSol(new BN(50), U64_MAX, [])
+      .accountsPartial({
+        taker: taker.publicKey,
+        maker: maker.publicKey,
+        mintA,
+        takerAtaA: getAssociatedTokenAddressSync(mintA, taker.publicKey),
+        escrow,
+        vault,
+        config,
+        treasury: treasury.publicKey,
+        referrer: null,
+        tokenProgramA: TOKEN_PROGRAM_ID,
+      })
+      .signers([taker])
+      .rpc();
+
+    // half a sol less the 1% fee
+    assert.equal((await connection.getBalance(maker.publicKey)) - makerBefore, 495_000_000);
+    assert.equal((await program.account.escrow.fetch(escrow)).remainingAmount.toNumber(), 50);
+  });
+
+  describe("collection offers", () => {
+    // prebuilt metaplex style accounts loaded through Anchor.toml , see tests/fixtures ,
+    // both NFT mints are still unminted and `nft-authority.json` is their mint authority
+    const fixtureCollection = new PublicKey("8UCYJzzN6x9dxSQcWeV7TS29hpqjQqo8jPxghEctMJRi");
+    const verifiedNft = new PublicKey("4iz2PTEeR4EzPycnyY2NRnr1N5VEaRMRF1azUThMzTPJ");
+    const verifiedMetadata = new PublicKey("E35LmRRDb7XynwZXbkbyRsY7R2cCftL379rWYm9yY53f");
+    const unverifiedNft = new PublicKey("91AEficdX6BHfuCcb5pPBuogtr5SNXTF2mpFWxic6Mp5");
+    const unverifiedMetadata = new PublicKey("B542eZd1qv9qUiNtHaDStCRCt9ZqrkSj3ZkSZyP2kRmF");
+    const nftAuthority = Keypair.fromSecretKey(
+      Uint8Array.from(JSON.parse(readFileSync(`${__dirname}/fixtures/nft-authority.json`, "utf8")))
+    );
+
+    const makeOffer = async (collection: PublicKey) => {
+      const escrow = escrowFor(await nextSeed());
+      await program.methods
+        .makeCollectionOffer(new BN(100), collection, new BN(0))
+        .accountsPartial({
+          maker: maker.publicKey,
+          mintA,
+          makerAtaA,
+          makerProfile,
+          escrow,
+          vault: getAssociatedTokenAddressSync(mintA, escrow, true),
+          tokenProgramA: TOKEN_PROGRAM_ID,
+        })
+        .signers([maker])
+        .rpc();
+      return escrow;
+    };
+
+    const takeOffer = (escrow: PublicKey, nft: PublicKey, nftMetadata: PublicKey | null, tokenProgramNft = TOKEN_PROGRAM_ID) =>
+      program.methods
+        .takeCollectionOffer()
+        .accountsPartial({
+          taker: taker.publicKey,
+          maker: maker.publicKey,
+          mintA,
+          nftMint: nft,
+          nftMetadata,
+          takerNftAta: getAssociatedTokenAddressSync(nft, taker.publicKey, false, tokenProgramNft),
+          makerNftAta: getAssociatedTokenAddressSync(nft, maker.publicKey, false, tokenProgramNft),
+          takerAtaA: getAssociatedTokenAddressSync(mintA, taker.publicKey),
+          escrow,
+          vault: getAssociatedTokenAddressSync(mintA, escrow, true),
+          tokenProgramA: TOKEN_PROGRAM_ID,
+          tokenProgramNft,
+        })
+        .signers([taker])
+        .rpc();
+
+    // the NFT ends up with the maker and the whole deposit with the taker
+    const assertFilled = async (escrow: PublicKey, nft: PublicKey, takerABefore: number, tokenProgramNft = TOKEN_PROGRAM_ID) => {
+      assert.equal(await balance(nft, maker.publicKey, tokenProgramNft), 1);
+      assert.equal((await balance(mintA, taker.publicKey)) - takerABefore, 100);
+      assert.isNull(await connection.getAccountInfo(escrow));
+    };
+
+    const mintFixtureNft = async (nft: PublicKey) => {
+      const ata = await getOrCreateAssociatedTokenAccount(connection, taker, nft, taker.publicKey);
+      await mintTo(connection, taker, nft, ata.address, nftAuthority, 1);
+    };
+
+    it("rejects NFTs outside the collection", async () => {
+      const escrow = await makeOffer(Keypair.generate().publicKey);
+
+      // a bare spl token NFT has neither a group member extension nor a metadata account
+      const nft = await createMint(connection, taker, taker.publicKey, null, 0);
+      const takerNftAta = (await getOrCreateAssociatedTokenAccount(connection, taker, nft, taker.publicKey)).address;
+      await mintTo(connection, taker, nft, takerNftAta, taker, 1);
+
+      await expectError(takeOffer(escrow, nft, null), "CollectionMismatch");
+    });
+
+    it("accepts a token 2022 group member of the collection", async () => {
+      // the group lives on its own mint , pointing at itself
+      const group = Keypair.generate();
+      const groupSpace = getMintLen([ExtensionType.GroupPointer]);
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(
+          SystemProgram.createAccount({
+            fromPubkey: taker.publicKey,
+            newAccountPubkey: group.publicKey,
+            space: groupSpace,
+            lamports: await connection.getMinimumBalanceForRentExemption(groupSpace),
+            programId: TOKEN_2022_PROGRAM_ID,
+          }),
+          createInitializeGroupPointerInstruction(group.publicKey, taker.publicKey, group.publicKey, TOKEN_2022_PROGRAM_ID),
+          createInitializeMintInstruction(group.publicKey, 0, taker.publicKey, null, TOKEN_2022_PROGRAM_ID)
+        ),
+        [taker, group]
+      );
+      await tokenGroupInitializeGroupWithRentTransfer(
+        connection, taker, group.publicKey, taker, taker.publicKey, BigInt(10), [], undefined, TOKEN_2022_PROGRAM_ID
+      );
+
+      // and the NFT carries a member extension pointing back at that group
+      const nft = Keypair.generate();
+      const nftSpace = getMintLen([ExtensionType.GroupMemberPointer]);
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(
+          SystemProgram.createAccount({
+            fromPubkey: taker.publicKey,
+            newAccountPubkey: nft.publicKey,
+            space: nftSpace,
+            lamports: await connection.getMinimumBalanceForRentExemption(nftSpace),
+            programId: TOKEN_2022_PROGRAM_ID,
+          }),
+          createInitializeGroupMemberPointerInstruction(nft.publicKey, taker.publicKey, nft.publicKey, TOKEN_2022_PROGRAM_ID),
+          createInitializeMintInstruction(nft.publicKey, 0, taker.publicKey, null, TOKEN_2022_PROGRAM_ID)
+        ),
+        [taker, nft]
+      );
+      await tokenGroupMemberInitializeWithRentTransfer(
+        connection, taker, nft.publicKey, taker, group.publicKey, taker, [], undefined, TOKEN_2022_PROGRAM_ID
+      );
+      const takerNftAta = await getOrCreateAssociatedTokenAccount(
+        connection, taker, nft.publicKey, taker.publicKey, false, undefined, undefined, TOKEN_2022_PROGRAM_ID
+      );
+      await mintTo(connection, taker, nft.publicKey, takerNftAta.address, taker, 1, [], undefined, TOKEN_2022_PROGRAM_ID);
+
+      // a member of another group is turned away
+      const elsewhere = await makeOffer(Keypair.generate().publicKey);
+      await expectError(takeOffer(elsewhere, nft.publicKey, null, TOKEN_2022_PROGRAM_ID), "CollectionMismatch");
+
+      const escrow = await makeOffer(group.publicKey);
+      const takerABefore = await balance(mintA, taker.publicKey);
+      await takeOffer(escrow, nft.publicKey, null, TOKEN_2022_PROGRAM_ID);
+      await assertFilled(escrow, nft.publicKey, takerABefore, TOKEN_2022_PROGRAM_ID);
+    });
+
+    it("accepts a metaplex NFT with a verified collection", async () => {
+      await mintFixtureNft(verifiedNft);
+      const escrow = await makeOffer(fixtureCollection);
+
+      // the metadata account is required for NFTs without a group member extension
+      await expectError(takeOffer(escrow, verifiedNft, null), "CollectionMismatch");
+
+      const takerABefore = await balance(mintA, taker.publicKey);
+      await takeOffer(escrow, verifiedNft, verifiedMetadata);
+      await assertFilled(escrow, verifiedNft, takerABefore);
+    });
+
+    it("rejects a metaplex NFT whose collection is not verified", async () => {
+      await mintFixtureNft(unverifiedNft);
+      const escrow = await makeOffer(fixtureCollection);
+
+      await expectError(takeOffer(escrow, unverifiedNft, unverifiedMetadata), "CollectionMismatch");
+      // nor can it borrow the metadata of a verified NFT
+      await expectError(takeOffer(escrow, unverifiedNft, verifiedMetadata), "CollectionMismatch");
+    });
+  });
+
+  it("make & refund", async () => {
+    const escrow = await make(200, 100);
+    const makerBefore = await balance(mintA, maker.publicKey);
+
+    await program.methods
+      .refund()
+      .accountsPartial({
+        maker: maker.publicKey,
+        mintA,
+        makerAtaA,
+        escrow,
+        vault: getAssociatedTokenAddressSync(mintA, escrow, true),
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([maker])
+      .rpc();
+
+    assert.isNull(await connection.getAccountInfo(escrow));
+    // the whole deposit comes back
+    assert.equal((await balance(mintA, maker.publicKey)) - makerBefore, 200);
+  });
+
+  it("allowlist orders only take a valid merkle proof", async () => {
+    const others = [Keypair.generate(), Keypair.generate(), Keypair.generate()].map((kp) => kp.publicKey);
+    const listed = allowlist([others[0], taker.publicKey, ...others.slice(1)]);
+    const escrow = await make(100, 100, null, null, listed.root);
+
+    // another member's path doesn't prove the taker
+    await expectError(take(escrow, 50, U64_MAX, null, null, listed.proof(others[0])), "TakerNotAllowed");
+
+    await take(escrow, 50, U64_MAX, null, null, listed.proof(taker.publicKey));
+    assert.equal((await program.account.escrow.fetch(escrow)).remainingAmount.toNumber(), 50);
+
+    // the taker's proof of another tree doesn't open an order whose tree leaves the taker out
+    const unlisted = allowlist(others);
+    const closed = await make(100, 100, null, null, unlisted.root);
+    await expectError(take(closed, 50, U64_MAX, null, null, listed.proof(taker.publicKey)), "TakerNotAllowed");
+  });
+
+  it("expired orders reject takes until the maker amends them", async () => {
+    const expiresAt = (await chainTime()) + 2;
+    const escrow = await make(100, 100, null, null, null, expiresAt);
+    await waitUntil(expiresAt);
+
+    await expectError(take(escrow, 50), "EscrowExpired");
+
+    const amend = (receive: BN | null, expires: BN | null) =>
+      program.methods
+        .amend(receive, expires)
+        .accountsPartial({ maker: maker.publicKey, escrow, orderBook: null })
+        .signers([maker])
+        .rpc();
+
+    // an expiry in the past is rejected rather than stored
+    await expectError(amend(null, new BN(expiresAt)), "InvalidExpiry");
+
+    // reprice to 2:1 and drop the expiry
+    await amend(new BN(200), new BN(0));
+    const state = await program.account.escrow.fetch(escrow);
+    assert.equal(state.recieveAmount.toNumber(), 200);
+    assert.equal(state.expiresAt.toNumber(), 0);
+
+    const makerBefore = await balance(mintB, maker.publicKey);
+    await take(escrow, 50);
+    assert.equal((await balance(mintB, maker.publicKey)) - makerBefore, 99);
+  });
+
+  // makes and fully takes a 100 token a escrow asking `receive` token b , each mint under its own token program ,
+  // the treasury's ATA of mint b is opened first unless `treasuryAta` is false
+  const swapAcrossPrograms = async (
+    a: PublicKey,
+    b: PublicKey,
+    programA: PublicKey,
+    programB: PublicKey,
+    receive: number,
+    treasuryAta = true
+  ) => {
+    const treasuryAtaB = treasuryAta
+      ? (await getOrCreateAssociatedTokenAccount(connection, taker, b, treasury.publicKey, false, undefined, undefined, programB)).address
+      : null;
+    const escrow = escrowFor(await nextSeed());
+    const vault = getAssociatedTokenAddressSync(a, escrow, true, programA);
+    await program.methods
+      .make(new BN(100), new BN(receive), null, null, new BN(0))
+      .accountsPartial({
+        maker: maker.publicKey,
+        mintA: a,
+        mintB: b,
+        makerAtaA: getAssociatedTokenAddressSync(a, maker.publicKey, false, programA),
+        makerProfile,
+        escrow,
+        vault,
+        orderBook: null,
+        tokenProgramA: programA,
+        tokenProgramB: programB,
+      })
+      .signers([maker])
+      .rpc();
+
+    await program.methods
+      .take(new BN(100), U64_MAX, [])
+      .accountsPartial({
+        taker: taker.publicKey,
+        maker: maker.publicKey,
+        mintA: a,
+        mintB: b,
+        takerAtaB: getAssociatedTokenAddressSync(b, taker.publicKey, false, programB),
+        takerAtaA: getAssociatedTokenAddressSync(a, taker.publicKey, false, programA),
+        makerAtaB: getAssociatedTokenAddressSync(b, maker.publicKey, false, programB),
+        escrow,
+        vault,
+        config,
+        treasuryAtaB,
+        referrerAtaB: null,
+        orderBook: null,
+        tokenProgramA: programA,
+        tokenProgramB: programB,
+      })
+      .signers([taker])
+      .rpc();
+    return escrow;
+  };
+
+  it("token 2022 transfer fees on mint b are paid by the taker", async () => {
+    // 1% transfer fee , capped far above anything this test moves
+    const feeMint = Keypair.generate();
+    const space = getMintLen([ExtensionType.TransferFeeConfig]);
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(
+        SystemProgram.createAccount({
+          fromPubkey: taker.publicKey,
+          newAccountPubkey: feeMint.publicKey,
+          space,
+          lamports: await connection.getMinimumBalanceForRentExemption(space),
+          programId: TOKEN_2022_PROGRAM_ID,
+        }),
+        createInitializeTransferFeeConfigInstruction(
+          feeMint.publicKey,
+          taker.publicKey,
+          taker.publicKey,
+          100,
+          BigInt(1_000_000),
+          TOKEN_2022_PROGRAM_ID
+        ),
+        createInitializeMintInstruction(feeMint.publicKey, 6, taker.publicKey, null, TOKEN_2022_PROGRAM_ID)
+      ),
+      [taker, feeMint]
+    );
+    const takerAta = await getOrCreateAssociatedTokenAccount(
+      connection, taker, feeMint.publicKey, taker.publicKey, false, undefined, undefined, TOKEN_2022_PROGRAM_ID
+    );
+    await mintTo(connection, taker, feeMint.publicKey, takerAta.address, taker, 100_000, [], undefined, TOKEN_2022_PROGRAM_ID);
+
+    await swapAcrossPrograms(mintA, feeMint.publicKey, TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, 1_000);
+
+    // the maker's 990 share of the 1_000 price is sent as 1_000 , its 10 transfer fee on top of the 10 protocol fee
+    assert.equal(await balance(feeMint.publicKey, maker.publicKey, TOKEN_2022_PROGRAM_ID), 990);
+    assert.equal(100_000 - (await balance(feeMint.publicKey, taker.publicKey, TOKEN_2022_PROGRAM_ID)), 1_000 + 10);
+  });
+
+  it("mint a and mint b can live under different token programs", async () => {
+    const mintA2022 = await createMint(connection, maker, maker.publicKey, null, 6, undefined, undefined, TOKEN_2022_PROGRAM_ID);
+    const makerAta = await getOrCreateAssociatedTokenAccount(
+      connection, maker, mintA2022, maker.publicKey, false, undefined, undefined, TOKEN_2022_PROGRAM_ID
+    );
+    await mintTo(connection, maker, mintA2022, makerAta.address, maker, 100, [], undefined, TOKEN_2022_PROGRAM_ID);
+    const makerBefore = await balance(mintB, maker.publicKey);
+
+    const escrow = await swapAcrossPrograms(mintA2022, mintB, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID, 300);
+
+    assert.equal(await balance(mintA2022, taker.publicKey, TOKEN_2022_PROGRAM_ID), 100);
+    assert.equal((await balance(mintB, maker.publicKey)) - makerBefore, 297);
+    assert.isNull(await connection.getAccountInfo(escrow));
+  });
+
+  it("auction prices decay and max_pay caps what the taker pays", async () => {
+    // 100 token a for 2_000 token b falling to 1_000 over ten seconds
+    const startTs = await chainTime();
+    const endTs = startTs + 10;
+    const escrow = escrowFor(await nextSeed());
+    await program.methods
+      .makeAuction(new BN(100), {
+        startPrice: new BN(2_000),
+        endPrice: new BN(1_000),
+        startTs: new BN(startTs),
+        endTs: new BN(endTs),
+      })
+      .accountsPartial({
+        maker: maker.publicKey,
+        mintA,
+        mintB,
+        makerAtaA,
+        makerProfile,
+        escrow,
+        vault: getAssociatedTokenAddressSync(mintA, escrow, true),
+        orderBook: null,
+        tokenProgramA: TOKEN_PROGRAM_ID,
+        tokenProgramB: TOKEN_PROGRAM_ID,
+      })
+      .signers([maker])
+      .rpc();
+
+    // 10 token a at the end price is 100 , too little while the auction is still high
+    const endQuote = new BN(100);
+    await expectError(take(escrow, 10, endQuote), "SlippageExceeded");
+
+    // part way through the price sits strictly between the start and the end price
+    await waitUntil(startTs + 3);
+    let makerBefore = await balance(mintB, maker.publicKey);
+    await take(escrow, 10);
+    // the maker's share is the price less the 1% fee
+    const midShare = (await balance(mintB, maker.publicKey)) - makerBefore;
+    assert.isAbove(midShare, 99);
+    assert.isBelow(midShare, 198);
+
+    // and it rests at the end price once the window is over
+    await waitUntil(endTs);
+    makerBefore = await balance(mintB, maker.publicKey);
+    await take(escrow, 10, endQuote);
+    assert.equal((await balance(mintB, maker.publicKey)) - makerBefore, 99);
+  });
+
+  it("fees need the treasury's own ATA and skip referrers without rent", async () => {
+    // takers never open the treasury's ATA , a pair it holds none of can't be taken while a fee is charged
+    const mintD = await createMint(connection, taker, taker.publicKey, null, 6);
+    const takerAtaD = await getOrCreateAssociatedTokenAccount(connection, taker, mintD, taker.publicKey);
+    await mintTo(connection, taker, mintD, takerAtaD.address, taker, 10_000);
+    await expectError(swapAcrossPrograms(mintA, mintD, TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID, 1_000, false), "TreasuryMismatch");
+
+    await swapAcrossPrograms(mintA, mintD, TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID, 1_000);
+    assert.equal(await balance(mintD, treasury.publicKey), 10);
+
+    // a sol order whose referrer share can't make a fresh wallet rent exempt pays it to the treasury
+    const escrow = escrowFor(await nextSeed());
+    const vault = getAssociatedTokenAddressSync(mintA, escrow, true);
+    await program.methods
+      .make(new BN(100), new BN(100_000), null, null, new BN(0))
+      .accountsPartial({
+        maker: maker.publicKey,
+        mintA,
+        mintB: null,
+        makerAtaA,
+        makerProfile,
+        escrow,
+        vault,
+        tokenProgramA: TOKEN_PROGRAM_ID,
+        tokenProgramB: TOKEN_PROGRAM_ID,
+      })
+      .signers([maker])
+      .rpc();
+
+    const freshReferrer = Keypair.generate().publicKey;
+    const treasuryBefore = await connection.getBalance(treasury.publicKey);
+    await program.methods
+      .takeSol(new BN(100), U64_MAX, [])
+      .accountsPartial({
+        taker: taker.publicKey,
+        maker: maker.publicKey,
+        mintA,
+        takerAtaA: getAssociatedTokenAddressSync(mintA, taker.publicKey),
+        escrow,
+        vault,
+        config,
+        treasury: treasury.publicKey,
+        referrer: freshReferrer,
+        tokenProgramA: TOKEN_PROGRAM_ID,
+      })
+      .signers([taker])
+      .rpc();
+
+    // 1% of 100_000 lamports , the 500 lamport referrer half included
+    assert.equal((await connection.getBalance(treasury.publicKey)) - treasuryBefore, 1_000);
+    assert.equal(await connection.getBalance(freshReferrer), 0);
+  });
+
+  it("escrows pushed out of a full order book or left with dust are unlisted", async () => {
+    // a pair of its own , so the book starts empty
+    const mintX = await createMint(connection, taker, taker.publicKey, null, 6);
+    const takerAtaX = await getOrCreateAssociatedTokenAccount(connection, taker, mintX, taker.publicKey);
+    await mintTo(connection, taker, mintX, takerAtaX.address, taker, 10_000);
+    const orderBook = PublicKey.findProgramAddressSync(
+      [Buffer.from("orderbook"), mintA.toBuffer(), mintX.toBuffer()],
+      program.programId
+    )[0];
+    const initBook = (admin: PublicKey, signers: Keypair[] = []) =>
+      program.methods
+        .initOrderBook(new BN(2))
+        .accountsPartial({ admin, config, mintA, mintB: mintX, orderBook })
+        .signers(signers)
+        .rpc();
+
+    // only the config admin opens books
+    await expectError(initBook(maker.publicKey, [maker]), "Unauthorized");
+    await initBook(provider.wallet.publicKey);
+
+    const makeListed = async (receive: number, evicted: PublicKey | null = null, deposit = 2) => {
+      const escrow = escrowFor(await nextSeed());
+      await program.methods
+        .make(new BN(deposit), new BN(receive), null, null, new BN(0))
+        .accountsPartial({
+          maker: maker.publicKey,
+          mintA,
+          mintB: mintX,
+          makerAtaA,
+          makerProfile,
+          escrow,
+          vault: getAssociatedTokenAddressSync(mintA, escrow, true),
+          orderBook,
+          evicted,
+          tokenProgramA: TOKEN_PROGRAM_ID,
+          tokenProgramB: TOKEN_PROGRAM_ID,
+        })
+        .signers([maker])
+        .rpc();
+      return escrow;
+    };
+    const takeOne = (escrow: PublicKey, book: PublicKey | null) =>
+      program.methods
+        .take(new BN(1), U64_MAX, [])
+        .accountsPartial({
+          taker: taker.publicKey,
+          maker: maker.publicKey,
+          mintA,
+          mintB: mintX,
+          takerAtaB: takerAtaX.address,
+          takerAtaA: getAssociatedTokenAddressSync(mintA, taker.publicKey),
+          makerAtaB: getAssociatedTokenAddressSync(mintX, maker.publicKey),
+          escrow,
+          vault: getAssociatedTokenAddressSync(mintA, escrow, true),
+          config,
+          // one token a of these orders is priced too low to carry a fee
+          treasuryAtaB: null,
+          referrerAtaB: null,
+          orderBook: book,
+          tokenProgramA: TOKEN_PROGRAM_ID,
+          tokenProgramB: TOKEN_PROGRAM_ID,
+        })
+        .signers([taker])
+        .rpc();
+    const listed = async () =>
+      (await program.account.orderBook.fetch(orderBook)).entries.map((entry) => entry.escrow.toBase58());
+
+    // deposits under the book's minimum are never listed
+    const dust = await makeListed(1, null, 1);
+    assert.isFalse((await program.account.escrow.fetch(dust)).indexed);
+    assert.notInclude(await listed(), dust.toBase58());
+
+    // fill all 32 slots , then a cheaper order pushes the priciest one out
+    const escrows: PublicKey[] = [];
+    for (let i = 0; i < 32; i++) {
+      escrows.push(await makeListed(100 + i));
+    }
+    const evicted = escrows[31];
+    await expectError(makeListed(10), "EvictedEscrowRequired");
+    await expectError(makeListed(10, escrows[30]), "EvictedEscrowRequired");
+    const cheapest = await makeListed(10, evicted);
+
+    assert.equal((await listed()).length, 32);
+    assert.equal((await listed())[0], cheapest.toBase58());
+    assert.notInclude(await listed(), evicted.toBase58());
+    // the make cleared the evicted escrow's flag , so its fills no longer need the book
+    assert.isFalse((await program.account.escrow.fetch(evicted)).indexed);
+    await takeOne(evicted, null);
+
+    // a listed escrow whose fill leaves less than the minimum drops out of the book
+    await expectError(takeOne(cheapest, null), "OrderBookRequired");
+    await takeOne(cheapest, orderBook);
+    assert.isFalse((await program.account.escrow.fetch(cheapest)).indexed);
+    assert.notInclude(await listed(), cheapest.toBase58());
+    await takeOne(cheapest, null);
+    assert.isNull(await connection.getAccountInfo(cheapest));
+  });
+
+  describe("baskets", () => {
+    let mintC: PublicKey;
+
+    const basketFor = (seed: BN) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("basket"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+
+    // a leg is (mint , token program) followed by the two token accounts it moves between
+    const leg = (mint: PublicKey, tokenProgram: PublicKey, from: PublicKey, to: PublicKey) =>
+      [mint, tokenProgram, from, to].map((pubkey, i) => ({ pubkey, isSigner: false, isWritable: i >= 2 }));
+    const ata = (mint: PublicKey, owner: PublicKey, tokenProgram = TOKEN_PROGRAM_ID) =>
+      getAssociatedTokenAddressSync(mint, owner, true, tokenProgram);
+
+    // offers token a (spl token) and token c (token 2022) for token b
+    const makeBasket = async (amountA: number, amountC: number, receive: number) => {
+      const basket = basketFor(await nextSeed());
+      await program.methods
+        .makeBasket([new BN(amountA), new BN(amountC)], [{ mint: mintB, amount: new BN(receive) }])
+        .accountsPartial({ maker: maker.publicKey, makerProfile, basket })
+        .remainingAccounts([
+          ...leg(mintA, TOKEN_PROGRAM_ID, makerAtaA, ata(mintA, basket)),
+          ...leg(mintC, TOKEN_2022_PROGRAM_ID, ata(mintC, maker.publicKey, TOKEN_2022_PROGRAM_ID), ata(mintC, basket, TOKEN_2022_PROGRAM_ID)),
+        ])
+        .signers([maker])
+        .rpc();
+      return basket;
+    };
+
+    const takeBasket = (basket: PublicKey, offeredLegs: ReturnType<typeof leg>[], requestedLegs: ReturnType<typeof leg>[]) =>
+      program.methods
+        .takeBasket()
+        .accountsPartial({ taker: taker.publicKey, maker: maker.publicKey, basket })
+        .remainingAccounts([...offeredLegs.flat(), ...requestedLegs.flat()])
+        .signers([taker])
+        .rpc();
+
+    const offeredToTaker = (basket: PublicKey) => [
+      leg(mintA, TOKEN_PROGRAM_ID, ata(mintA, basket), ata(mintA, taker.publicKey)),
+      leg(mintC, TOKEN_2022_PROGRAM_ID, ata(mintC, basket, TOKEN_2022_PROGRAM_ID), ata(mintC, taker.publicKey, TOKEN_2022_PROGRAM_ID)),
+    ];
+    const requestedFromTaker = [leg(mintB, TOKEN_PROGRAM_ID, takerAtaB, ata(mintB, maker.publicKey))];
+
+    before(async () => {
+      mintC = await createMint(connection, maker, maker.publicKey, null, 6, undefined, undefined, TOKEN_2022_PROGRAM_ID);
+      const makerAtaC = await getOrCreateAssociatedTokenAccount(
+        connection, maker, mintC, maker.publicKey, false, undefined, undefined, TOKEN_2022_PROGRAM_ID
+      );
+      await mintTo(connection, maker, mintC, makerAtaC.address, maker, 1_000, [], undefined, TOKEN_2022_PROGRAM_ID);
+    });
+
+    it("swaps two mints for one in a single take", async () => {
+      const basket = await makeBasket(100, 50, 200);
+      const makerBefore = await balance(mintB, maker.publicKey);
+      const takerABefore = await balance(mintA, taker.publicKey);
+
+      await takeBasket(basket, offeredToTaker(basket), requestedFromTaker);
+
+      assert.equal((await balance(mintB, maker.publicKey)) - makerBefore, 200);
+      assert.equal((await balance(mintA, taker.publicKey)) - takerABefore, 100);
+      assert.equal(await balance(mintC, taker.publicKey, TOKEN_2022_PROGRAM_ID), 50);
+      assert.isNull(await connection.getAccountInfo(basket));
+      assert.isNull(await connection.getAccountInfo(ata(mintA, basket)));
+    });
+
+    it("rejects misplaced leg accounts and refunds the maker", async () => {
+      const basket = await makeBasket(10, 20, 30);
+      const [legA, legC] = offeredToTaker(basket);
+
+      // legs out of order
+      await expectError(takeBasket(basket, [legC, legA], requestedFromTaker), "BasketAccountMismatch");
+      // vault and taker ATA swapped within a leg
+      const swapped = leg(mintA, TOKEN_PROGRAM_ID, ata(mintA, taker.publicKey), ata(mintA, basket));
+      await expectError(takeBasket(basket, [swapped, legC], requestedFromTaker), "BasketAccountMismatch");
+      // token b paid to an ATA that isn't the maker's
+      const wrongMakerAta = [leg(mintB, TOKEN_PROGRAM_ID, takerAtaB, treasuryAtaB)];
+      await expectError(takeBasket(basket, [legA, legC], wrongMakerAta), "BasketAccountMismatch");
+      // token c under the wrong token program
+      const wrongProgram = leg(mintC, TOKEN_PROGRAM_ID, ata(mintC, basket), ata(mintC, taker.publicKey));
+      await expectError(takeBasket(basket, [legA, wrongProgram], requestedFromTaker), "BasketAccountMismatch");
+
+      const makerCBefore = await balance(mintC, maker.publicKey, TOKEN_2022_PROGRAM_ID);
+      await program.methods
+        .refundBasket()
+        .accountsPartial({ maker: maker.publicKey, basket })
+        .remainingAccounts([
+          ...leg(mintA, TOKEN_PROGRAM_ID, ata(mintA, basket), makerAtaA),
+          ...leg(mintC, TOKEN_2022_PROGRAM_ID, ata(mintC, basket, TOKEN_2022_PROGRAM_ID), ata(mintC, maker.publicKey, TOKEN_2022_PROGRAM_ID)),
+        ])
+        .signers([maker])
+        .rpc();
+
+      assert.equal((await balance(mintC, maker.publicKey, TOKEN_2022_PROGRAM_ID)) - makerCBefore, 20);
+      assert.isNull(await connection.getAccountInfo(basket));
+    });
   });
 });
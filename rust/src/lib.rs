@@ -155,4 +155,13 @@ to send transaction",
         let base58 = bs58::encode(wallet).into_string();
         println!("{:?}", base58);
     }
+
+    #[test]
+    fn amm_quote() {
+        // same math the amm program runs , so the quote matches what the swap pays out
+        let quote = amm_curve::swap_out(10_000, 1_100_000, 4_400_000, 30).unwrap();
+        // 0.3% of 10_000 is 30 , then 4_400_000 * 9_970 / 1_109_970 = 39_521.7 rounded down
+        assert_eq!(quote.fee, 30);
+        assert_eq!(quote.amount_out, 39_521);
+    }
 }
@@ -2,3 +2,13 @@ use anchor_lang::prelude::*;
 
 #[constant]
 pub const SEED: &str = "anchor";
+
+// permission bits a `SessionToken` can grant to a temporary key, only escrow actions can be delegated
+#[constant]
+pub const SESSION_SET_RECEIVER: u8 = 1 << 0;
+#[constant]
+pub const SESSION_APPROVE_RELEASE: u8 = 1 << 1;
+
+// how many escrows a single session can be scoped to, an empty list means any escrow
+#[constant]
+pub const MAX_SESSION_ESCROWS: usize = 8;
@@ -4,4 +4,40 @@ use anchor_lang::prelude::*;
 pub enum ErrorCode {
     #[msg("Custom error message")]
     CustomError,
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Due date must be in the future")]
+    InvalidDueDate,
+    #[msg("Invoice is past its due date")]
+    InvoiceOverdue,
+    #[msg("Subscription period must be greater than zero")]
+    InvalidPeriod,
+    #[msg("No subscription period is due yet")]
+    NothingToCollect,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Signer is neither the owner nor a delegated session key")]
+    Unauthorized,
+    #[msg("Session key has expired")]
+    SessionExpired,
+    #[msg("Session key is not permitted to perform this action")]
+    SessionNotPermitted,
+    #[msg("Too many escrows for one session")]
+    TooManySessionEscrows,
+    #[msg("Escrow has no receiver yet")]
+    ReceiverNotSet,
+    #[msg("Release needs the maker's signature or approval")]
+    ReleaseNotApproved,
+    #[msg("Batch accounts must come in (escrow, vault, recipient ATA) tuples, plus the receiver for releases")]
+    InvalidBatchAccounts,
+    #[msg("Escrow, vault or recipient account does not match")]
+    InvalidEscrowAccount,
+    #[msg("Escrow has not expired yet")]
+    EscrowNotExpired,
+    #[msg("Escrow already has a receiver")]
+    EscrowAlreadyAccepted,
+    #[msg("Expiry must be in the future")]
+    InvalidExpiry,
+    #[msg("Vault or recipient token account is frozen")]
+    TokenAccountFrozen,
 }
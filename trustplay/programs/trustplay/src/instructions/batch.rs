@@ -0,0 +1,171 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::error::ErrorCode;
+use crate::state::EscrowState;
+use crate::utils::{close_vault, transfer_from_vault};
+
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Settlement {
+    // vault goes to the ATA of `escrow.receiver`, and its rent to the receiver like a single `release`
+    Release,
+    // vault goes back to the ATA of the maker
+    Refund,
+}
+
+impl Settlement {
+    // every escrow in the batch is passed as (escrow, vault, recipient ATA) in `remaining_accounts`,
+    // releases add the receiver wallet that gets the vault rent
+    pub fn tuple_len(self) -> usize {
+        match self {
+            Settlement::Release => 4,
+            Settlement::Refund => 3,
+        }
+    }
+}
+
+#[event]
+pub struct BatchSettled {
+    pub maker: Pubkey,
+    pub released: bool,
+    pub escrows: Vec<Pubkey>,
+    // `results[i]` tells whether `escrows[i]` was settled or skipped
+    pub results: Vec<bool>,
+}
+
+#[derive(Accounts)]
+pub struct BatchSettle<'info> {
+    // every escrow in the batch must belong to this maker, it gets the escrow rent back
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+struct BatchItem<'info> {
+    escrow: Account<'info, EscrowState>,
+    vault: InterfaceAccount<'info, TokenAccount>,
+    recipient: InterfaceAccount<'info, TokenAccount>,
+    // where the vault rent goes
+    rent_destination: AccountInfo<'info>,
+}
+
+impl<'info> BatchSettle<'info> {
+    fn load_item(
+        &self,
+        accounts: &'info [AccountInfo<'info>],
+        settlement: Settlement,
+    ) -> Result<BatchItem<'info>> {
+        let escrow = Account::<EscrowState>::try_from(&accounts[0])?;
+        require_keys_eq!(escrow.maker, self.maker.key(), ErrorCode::InvalidEscrowAccount);
+        require_keys_eq!(escrow.mint_a, self.mint_a.key(), ErrorCode::InvalidEscrowAccount);
+
+        // same check `seeds` + `bump = escrow.bump` would do in an accounts struct
+        let seed_bytes = escrow.seed.to_le_bytes();
+        let expected = Pubkey::create_program_address(
+            &[b"escrow", escrow.maker.as_ref(), seed_bytes.as_ref(), &[escrow.bump]],
+            &crate::ID,
+        )
+        .map_err(|_| error!(ErrorCode::InvalidEscrowAccount))?;
+        require_keys_eq!(expected, escrow.key(), ErrorCode::InvalidEscrowAccount);
+
+        let vault = InterfaceAccount::<TokenAccount>::try_from(&accounts[1])?;
+        require_keys_eq!(vault.owner, escrow.key(), ErrorCode::InvalidEscrowAccount);
+        require_keys_eq!(vault.mint, escrow.mint_a, ErrorCode::InvalidEscrowAccount);
+
+        let (recipient_owner, rent_destination) = match settlement {
+            Settlement::Release => {
+                require!(escrow.receiver != Pubkey::default(), ErrorCode::ReceiverNotSet);
+                require_keys_eq!(accounts[3].key(), escrow.receiver, ErrorCode::InvalidEscrowAccount);
+                (escrow.receiver, accounts[3].clone())
+            }
+            Settlement::Refund => (escrow.maker, self.maker.to_account_info()),
+        };
+        let recipient = InterfaceAccount::<TokenAccount>::try_from(&accounts[2])?;
+        require_keys_eq!(recipient.owner, recipient_owner, ErrorCode::InvalidEscrowAccount);
+        require_keys_eq!(recipient.mint, escrow.mint_a, ErrorCode::InvalidEscrowAccount);
+
+        // everything the transfer and close CPIs would reject is caught here, so with `skip_failed`
+        // a bad escrow is skipped instead of aborting the whole batch
+        for token_account in [&accounts[1], &accounts[2]] {
+            require_keys_eq!(*token_account.owner, self.token_program.key(), ErrorCode::InvalidEscrowAccount);
+            require!(token_account.is_writable, ErrorCode::InvalidEscrowAccount);
+        }
+        require!(!vault.is_frozen() && !recipient.is_frozen(), ErrorCode::TokenAccountFrozen);
+        require!(accounts[0].is_writable && rent_destination.is_writable, ErrorCode::InvalidEscrowAccount);
+
+        Ok(BatchItem { escrow, vault, recipient, rent_destination })
+    }
+
+    fn settle_item(&self, item: BatchItem<'info>) -> Result<()> {
+        let seed_bytes = item.escrow.seed.to_le_bytes();
+        let seeds = &[
+            b"escrow",
+            item.escrow.maker.as_ref(),
+            seed_bytes.as_ref(),
+            &[item.escrow.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        transfer_from_vault(
+            self.token_program.to_account_info(),
+            item.vault.to_account_info(),
+            &self.mint_a,
+            item.recipient.to_account_info(),
+            item.escrow.to_account_info(),
+            signer_seeds,
+            item.vault.amount,
+        )?;
+
+        close_vault(
+            self.token_program.to_account_info(),
+            item.vault.to_account_info(),
+            item.rent_destination,
+            item.escrow.to_account_info(),
+            signer_seeds,
+        )?;
+
+        item.escrow.close(self.maker.to_account_info())
+    }
+}
+
+pub fn settle_batch<'info>(
+    ctx: Context<'_, '_, 'info, 'info, BatchSettle<'info>>,
+    settlement: Settlement,
+    skip_failed: bool,
+) -> Result<()> {
+    let remaining = ctx.remaining_accounts;
+    let tuple_len = settlement.tuple_len();
+    require!(
+        !remaining.is_empty() && remaining.chunks_exact(tuple_len).remainder().is_empty(),
+        ErrorCode::InvalidBatchAccounts
+    );
+
+    let mut escrows = Vec::with_capacity(remaining.len() / tuple_len);
+    let mut results = Vec::with_capacity(remaining.len() / tuple_len);
+
+    for accounts in remaining.chunks(tuple_len) {
+        escrows.push(accounts[0].key());
+
+        match ctx.accounts.load_item(accounts, settlement) {
+            Ok(item) => {
+                ctx.accounts.settle_item(item)?;
+                results.push(true);
+            }
+            Err(err) if skip_failed => {
+                msg!("skipping escrow {}: {}", accounts[0].key(), err);
+                results.push(false);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    emit!(BatchSettled {
+        maker: ctx.accounts.maker.key(),
+        released: settlement == Settlement::Release,
+        escrows,
+        results,
+    });
+    Ok(())
+}
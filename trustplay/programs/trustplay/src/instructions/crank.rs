@@ -0,0 +1,93 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::error::ErrorCode;
+use crate::state::EscrowState;
+use crate::utils::{close_vault, transfer_from_vault};
+
+
+#[derive(Accounts)]
+pub struct CrankExpired<'info> {
+    // anyone can reap an expired escrow
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program,
+    )]
+    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        has_one = mint_a,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, EscrowState>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> CrankExpired<'info> {
+    pub fn refund_expired(&mut self) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            self.escrow.expires_at != 0 && now >= self.escrow.expires_at,
+            ErrorCode::EscrowNotExpired
+        );
+        require!(self.escrow.receiver == Pubkey::default(), ErrorCode::EscrowAlreadyAccepted);
+
+        let seed_bytes = self.escrow.seed.to_le_bytes();
+        let seeds = &[
+            b"escrow",
+            self.escrow.maker.as_ref(),
+            seed_bytes.as_ref(),
+            &[self.escrow.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        transfer_from_vault(
+            self.token_program.to_account_info(),
+            self.vault.to_account_info(),
+            &self.mint_a,
+            self.maker_ata_a.to_account_info(),
+            self.escrow.to_account_info(),
+            signer_seeds,
+            self.vault.amount,
+        )?;
+
+        close_vault(
+            self.token_program.to_account_info(),
+            self.vault.to_account_info(),
+            self.maker.to_account_info(),
+            self.escrow.to_account_info(),
+            signer_seeds,
+        )
+    }
+
+    pub fn pay_tip(&mut self) -> Result<()> {
+        if self.cranker.key() == self.maker.key() {
+            return Ok(());
+        }
+
+        // the maker funded the tip at `make`, the rent goes back to the maker through `close = maker`
+        let tip = self.escrow.crank_tip;
+        if tip > 0 {
+            self.escrow.sub_lamports(tip)?;
+            self.cranker.add_lamports(tip)?;
+        }
+        Ok(())
+    }
+}
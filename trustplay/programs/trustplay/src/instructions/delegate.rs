@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::MAX_SESSION_ESCROWS;
+use crate::error::ErrorCode;
+use crate::state::SessionToken;
+
+
+#[derive(Accounts)]
+pub struct CreateSession<'info> {
+    // the wallet delegating its permissions
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    /// CHECK: Only storing the pubkey of the temporary key
+    pub session_key: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + SessionToken::INIT_SPACE,
+        seeds = [b"session", authority.key().as_ref(), session_key.key().as_ref()],
+        bump,
+    )]
+    pub session: Account<'info, SessionToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateSession<'info> {
+    pub fn create_session(
+        &mut self,
+        permissions: u8,
+        escrows: Vec<Pubkey>,
+        max_amount: u64,
+        expires_at: i64,
+        bumps: &CreateSessionBumps,
+    ) -> Result<()> {
+        require!(escrows.len() <= MAX_SESSION_ESCROWS, ErrorCode::TooManySessionEscrows);
+        require!(expires_at > Clock::get()?.unix_timestamp, ErrorCode::SessionExpired);
+
+        self.session.set_inner(SessionToken {
+            authority: self.authority.key(),
+            session_key: self.session_key.key(),
+            permissions,
+            escrows,
+            max_amount,
+            expires_at,
+            bump: bumps.session,
+        });
+        Ok(())
+    }
+}
+
+
+#[derive(Accounts)]
+pub struct RevokeSession<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        close = authority,
+        has_one = authority,
+        seeds = [b"session", authority.key().as_ref(), session.session_key.as_ref()],
+        bump = session.bump,
+    )]
+    pub session: Account<'info, SessionToken>,
+}
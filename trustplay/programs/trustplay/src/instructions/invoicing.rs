@@ -0,0 +1,165 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked};
+
+use crate::error::ErrorCode;
+use crate::state::{EscrowState, InvoiceState, MakerProfile};
+
+
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct CreateInvoice<'info> {
+    // the receiver opens the invoice and pays its rent
+    #[account(mut)]
+    pub receiver: Signer<'info>,
+    /// CHECK: Only storing the pubkey of whoever is allowed to pay
+    pub payer: UncheckedAccount<'info>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        init,
+        payer = receiver,
+        space = 8 + InvoiceState::INIT_SPACE,
+        seeds = [b"invoice", receiver.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub invoice: Account<'info, InvoiceState>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateInvoice<'info> {
+    pub fn create_invoice(
+        &mut self,
+        seed: u64,
+        amount: u64,
+        memo_hash: [u8; 32],
+        due_date: i64,
+        bumps: &CreateInvoiceBumps,
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            due_date == 0 || due_date > Clock::get()?.unix_timestamp,
+            ErrorCode::InvalidDueDate
+        );
+
+        self.invoice.set_inner(InvoiceState {
+            seed,
+            receiver: self.receiver.key(),
+            payer: self.payer.key(),
+            mint_a: self.mint_a.key(),
+            amount,
+            memo_hash,
+            due_date,
+            bump: bumps.invoice,
+        });
+        Ok(())
+    }
+}
+
+
+#[derive(Accounts)]
+pub struct PayInvoice<'info> {
+    // the payer becomes the maker of the escrow funded below
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    // gets back the rent of the invoice account
+    #[account(mut)]
+    pub receiver: SystemAccount<'info>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = payer,
+        associated_token::token_program = token_program,
+    )]
+    pub payer_ata_a: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        close = receiver,
+        has_one = payer,
+        has_one = receiver,
+        has_one = mint_a,
+        seeds = [b"invoice", receiver.key().as_ref(), invoice.seed.to_le_bytes().as_ref()],
+        bump = invoice.bump,
+    )]
+    pub invoice: Account<'info, InvoiceState>,
+    // the escrow is numbered like any other escrow of the payer
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + MakerProfile::INIT_SPACE,
+        seeds = [b"profile", payer.key().as_ref()],
+        bump,
+    )]
+    pub payer_profile: Account<'info, MakerProfile>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + EscrowState::INIT_SPACE + 32,
+        seeds = [b"escrow", payer.key().as_ref(), payer_profile.escrow_count.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub escrow: Account<'info, EscrowState>,
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> PayInvoice<'info> {
+    pub fn pay_invoice(&mut self, bumps: &PayInvoiceBumps) -> Result<()> {
+        // an overdue invoice can only be cancelled, the receiver has to ask again
+        let due_date = self.invoice.due_date;
+        require!(
+            due_date == 0 || Clock::get()?.unix_timestamp <= due_date,
+            ErrorCode::InvoiceOverdue
+        );
+
+        let seed = self.payer_profile.next_seed(self.payer.key(), bumps.payer_profile)?;
+
+        // same escrow a `make` would produce, except the receiver is already set
+        self.escrow.set_inner(EscrowState {
+            seed,
+            maker: self.payer.key(),
+            mint_a: self.mint_a.key(),
+            amount: self.invoice.amount,
+            bump: bumps.escrow,
+            receiver: self.invoice.receiver,
+            release_approved: false,
+            expires_at: 0,
+            crank_tip: 0,
+        });
+
+        let cpi_accounts = TransferChecked {
+            from: self.payer_ata_a.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+            to: self.vault.to_account_info(),
+            authority: self.payer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+
+        transfer_checked(cpi_ctx, self.invoice.amount, self.mint_a.decimals)?;
+        Ok(())
+    }
+}
+
+
+#[derive(Accounts)]
+pub struct CancelInvoice<'info> {
+    #[account(mut)]
+    pub receiver: Signer<'info>,
+    #[account(
+        mut,
+        close = receiver,
+        has_one = receiver,
+        seeds = [b"invoice", receiver.key().as_ref(), invoice.seed.to_le_bytes().as_ref()],
+        bump = invoice.bump,
+    )]
+    pub invoice: Account<'info, InvoiceState>,
+}
@@ -1,13 +1,13 @@
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
+use anchor_lang::system_program::{transfer, Transfer};
 use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked};
 
-use crate::state::EscrowState;
+use crate::error::ErrorCode;
+use crate::state::{EscrowState, MakerProfile};
 
 
 #[derive(Accounts)]
-#[instruction(seeds: u64)] // this means i would pass a type u8 to the seeds field inside the instruction
-// when using multiple instructions params, keep them in the same order in the instruction as provided here
 pub struct Make<'info> {
     #[account(mut)]
     pub maker: Signer<'info>,
@@ -25,6 +25,15 @@ pub struct Make<'info> {
     // the assumption here is that the maker has already created the associated token account
     // since they want to exchange token a for token b they must already have an ATA to store token a
     pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,  
+    // the escrow seed comes from this counter, so the client never has to pick one
+    #[account(
+        init_if_needed,
+        payer = maker,
+        space = 8 + MakerProfile::INIT_SPACE,
+        seeds = [b"profile", maker.key().as_ref()],
+        bump,
+    )]
+    pub maker_profile: Account<'info, MakerProfile>,
     #[account(
         init, 
         payer = maker,
@@ -33,7 +42,7 @@ pub struct Make<'info> {
         // &[u8], so integers are converted to bytes by using to_le_bytes()
         // to_le_bytes() returns a byte version of the seed and as_ref() returns a reference to that byte which
         // together is a byte slice
-        seeds = [b"escrow", maker.key().as_ref(), seeds.to_le_bytes().as_ref()], // 
+        seeds = [b"escrow", maker.key().as_ref(), maker_profile.escrow_count.to_le_bytes().as_ref()],
         bump,
     )]
     pub escrow: Account<'info, EscrowState>,
@@ -52,15 +61,35 @@ pub struct Make<'info> {
 }
 
 impl<'info> Make<'info> {
-    pub fn make(&mut self, seed: u64, amount: u64, bumps: &MakeBumps) -> Result<()> {
+    pub fn make(&mut self, amount: u64, expires_at: i64, crank_tip: u64, bumps: &MakeBumps) -> Result<()> {
+        // an expiry in the past would let anyone crank the escrow in the same slot it was made
+        require!(
+            expires_at == 0 || expires_at > Clock::get()?.unix_timestamp,
+            ErrorCode::InvalidExpiry
+        );
+
+        let seed = self.maker_profile.next_seed(self.maker.key(), bumps.maker_profile)?;
         self.escrow.set_inner(EscrowState {
             seed,
             maker: self.maker.key(),
             mint_a: self.mint_a.key(),
             amount,
             bump: bumps.escrow,
-            receiver: Pubkey::default()
+            receiver: Pubkey::default(),
+            release_approved: false,
+            expires_at,
+            crank_tip,
         });
+
+        // the tip sits in the escrow account with its rent, and goes back to the maker on close if nobody cranks
+        if crank_tip > 0 {
+            let cpi_accounts = Transfer {
+                from: self.maker.to_account_info(),
+                to: self.escrow.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), cpi_accounts);
+            transfer(cpi_ctx, crank_tip)?;
+        }
         Ok(())
     }
 
@@ -10,5 +10,17 @@ pub use refund::*;
 pub mod take;
 pub use take::*;
 
+pub mod invoicing;
+pub use invoicing::*;
 
+pub mod subscribe;
+pub use subscribe::*;
 
+pub mod delegate;
+pub use delegate::*;
+
+pub mod batch;
+pub use batch::*;
+
+pub mod crank;
+pub use crank::*;
@@ -2,9 +2,9 @@
 use anchor_lang::prelude::*;  
 use anchor_spl::token_interface::{TokenInterface, Mint, TokenAccount};
 use anchor_spl::associated_token::AssociatedToken;
-use anchor_spl::token::{TransferChecked, transfer_checked, close_account, CloseAccount};
 
 use crate::state::EscrowState;
+use crate::utils::{close_vault, transfer_from_vault};
 
 
 #[derive(Accounts)]
@@ -47,17 +47,6 @@ pub struct Refund<'info> {
 
 impl<'info> Refund<'info> {
     pub fn refund(&mut self) -> Result<()> {
-        let cpi_program = self.token_program.to_account_info();
-        
-        // use the TransferChecked struct to transfer tokens from the maker ATA to the escrow vault
-        // not the Usual Transfer Struct we use for normal SOL transfer
-        let cpi_accounts = TransferChecked {
-            from: self.vault.to_account_info(),
-            mint: self.mint_a.to_account_info(),
-            to: self.maker_ata_a.to_account_info(),
-            authority: self.escrow.to_account_info(), // since the escrow account owns the from account (vault account)
-        };
-
         let escrow_seed = self.escrow.seed.to_le_bytes();
 
         let seeds = [
@@ -69,24 +58,21 @@ impl<'info> Refund<'info> {
 
         let signer_seeds = &[&seeds[..]];
 
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
-
         // transfer the tokens
         // use amount when you want to get the amount of token a token account holds
         // use get_lamports() when you want to get the amount of SOL/lamports stored on an account
-        transfer_checked(cpi_ctx, self.vault.amount, self.mint_a.decimals)?;
-
-        Ok(())
+        transfer_from_vault(
+            self.token_program.to_account_info(),
+            self.vault.to_account_info(),
+            &self.mint_a,
+            self.maker_ata_a.to_account_info(),
+            self.escrow.to_account_info(), // since the escrow account owns the from account (vault account)
+            signer_seeds,
+            self.vault.amount,
+        )
     }
 
     pub fn close(&mut self) -> Result<()> {
-        // close the escrow account and the vault account here
-        let cpi_accounts = CloseAccount {
-            account: self.vault.to_account_info(),
-            destination: self.maker.to_account_info(),
-            authority: self.escrow.to_account_info(),
-        };
-
         let seed_bytes = self.escrow.seed.to_le_bytes();
 
         // this seeds needs to match that used in the account struct
@@ -94,10 +80,13 @@ impl<'info> Refund<'info> {
 
         let signers_seeds = [&seeds[..]];
 
-        let cpi_ctx = CpiContext::new_with_signer(self.system_program.to_account_info(), cpi_accounts, &signers_seeds);
-
-        close_account(cpi_ctx)?;
-
-        Ok(())
+        // close the vault account here, the escrow account is closed by `close = maker`
+        close_vault(
+            self.token_program.to_account_info(),
+            self.vault.to_account_info(),
+            self.maker.to_account_info(),
+            self.escrow.to_account_info(),
+            &signers_seeds,
+        )
     }
-}
\ No newline at end of file
+}
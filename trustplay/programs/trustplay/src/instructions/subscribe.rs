@@ -0,0 +1,262 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked};
+
+use crate::error::ErrorCode;
+use crate::state::SubscriptionState;
+use crate::utils::{amount_with_transfer_fee, close_vault, transfer_from_vault};
+
+
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct Subscribe<'info> {
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+    /// CHECK: Only storing the pubkey of the service provider
+    pub receiver: UncheckedAccount<'info>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = subscriber,
+        associated_token::token_program = token_program,
+    )]
+    pub subscriber_ata_a: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = subscriber,
+        space = 8 + SubscriptionState::INIT_SPACE,
+        seeds = [b"subscription", subscriber.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub subscription: Account<'info, SubscriptionState>,
+    #[account(
+        init,
+        payer = subscriber,
+        associated_token::mint = mint_a,
+        associated_token::authority = subscription,
+        associated_token::token_program = token_program,
+    )]
+    // holds every funded period until it is pulled or refunded
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> Subscribe<'info> {
+    pub fn subscribe(
+        &mut self,
+        seed: u64,
+        amount_per_period: u64,
+        period: i64,
+        periods: u64,
+        bumps: &SubscribeBumps,
+    ) -> Result<()> {
+        require!(amount_per_period > 0 && periods > 0, ErrorCode::InvalidAmount);
+        require!(period > 0, ErrorCode::InvalidPeriod);
+
+        self.subscription.set_inner(SubscriptionState {
+            seed,
+            subscriber: self.subscriber.key(),
+            receiver: self.receiver.key(),
+            mint_a: self.mint_a.key(),
+            amount_per_period,
+            period,
+            periods,
+            periods_claimed: 0,
+            start_ts: Clock::get()?.unix_timestamp,
+            bump: bumps.subscription,
+        });
+        Ok(())
+    }
+
+    pub fn deposit(&mut self) -> Result<()> {
+        let amount = self
+            .subscription
+            .amount_per_period
+            .checked_mul(self.subscription.periods)
+            .ok_or(ErrorCode::MathOverflow)?;
+        // every period is pulled out of the vault in full, so the subscriber also covers the transfer fee on the way in
+        let gross = amount_with_transfer_fee(&self.mint_a, amount)?;
+
+        let cpi_accounts = TransferChecked {
+            from: self.subscriber_ata_a.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+            to: self.vault.to_account_info(),
+            authority: self.subscriber.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+
+        transfer_checked(cpi_ctx, gross, self.mint_a.decimals)
+    }
+}
+
+
+#[derive(Accounts)]
+pub struct Collect<'info> {
+    #[account(mut)]
+    pub receiver: Signer<'info>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        has_one = receiver,
+        has_one = mint_a,
+        seeds = [b"subscription", subscription.subscriber.as_ref(), subscription.seed.to_le_bytes().as_ref()],
+        bump = subscription.bump,
+    )]
+    pub subscription: Account<'info, SubscriptionState>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = subscription,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = receiver,
+        associated_token::mint = mint_a,
+        associated_token::authority = receiver,
+        associated_token::token_program = token_program,
+    )]
+    pub receiver_ata: InterfaceAccount<'info, TokenAccount>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> Collect<'info> {
+    pub fn collect(&mut self) -> Result<()> {
+        let due = self.subscription.periods_due(Clock::get()?.unix_timestamp);
+        require!(due > 0, ErrorCode::NothingToCollect);
+
+        let amount = self
+            .subscription
+            .amount_per_period
+            .checked_mul(due)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let seed_bytes = self.subscription.seed.to_le_bytes();
+        let seeds = &[
+            b"subscription",
+            self.subscription.subscriber.as_ref(),
+            seed_bytes.as_ref(),
+            &[self.subscription.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        transfer_from_vault(
+            self.token_program.to_account_info(),
+            self.vault.to_account_info(),
+            &self.mint_a,
+            self.receiver_ata.to_account_info(),
+            self.subscription.to_account_info(),
+            signer_seeds,
+            amount,
+        )?;
+
+        self.subscription.periods_claimed += due;
+        Ok(())
+    }
+}
+
+
+#[derive(Accounts)]
+pub struct CancelSubscription<'info> {
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+    pub receiver: SystemAccount<'info>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = subscriber,
+        associated_token::token_program = token_program,
+    )]
+    pub subscriber_ata_a: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        close = subscriber,
+        has_one = subscriber,
+        has_one = receiver,
+        has_one = mint_a,
+        seeds = [b"subscription", subscriber.key().as_ref(), subscription.seed.to_le_bytes().as_ref()],
+        bump = subscription.bump,
+    )]
+    pub subscription: Account<'info, SubscriptionState>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = subscription,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    // periods already earned but not pulled yet still go to the receiver
+    #[account(
+        init_if_needed,
+        payer = subscriber,
+        associated_token::mint = mint_a,
+        associated_token::authority = receiver,
+        associated_token::token_program = token_program,
+    )]
+    pub receiver_ata: InterfaceAccount<'info, TokenAccount>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> CancelSubscription<'info> {
+    pub fn cancel(&mut self) -> Result<()> {
+        let due = self.subscription.periods_due(Clock::get()?.unix_timestamp);
+        let earned = self
+            .subscription
+            .amount_per_period
+            .checked_mul(due)
+            .ok_or(ErrorCode::MathOverflow)?
+            .min(self.vault.amount);
+        let unearned = self.vault.amount - earned;
+
+        let seed_bytes = self.subscription.seed.to_le_bytes();
+        let seeds = &[
+            b"subscription",
+            self.subscriber.key.as_ref(),
+            seed_bytes.as_ref(),
+            &[self.subscription.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        if earned > 0 {
+            transfer_from_vault(
+                self.token_program.to_account_info(),
+                self.vault.to_account_info(),
+                &self.mint_a,
+                self.receiver_ata.to_account_info(),
+                self.subscription.to_account_info(),
+                signer_seeds,
+                earned,
+            )?;
+        }
+
+        if unearned > 0 {
+            transfer_from_vault(
+                self.token_program.to_account_info(),
+                self.vault.to_account_info(),
+                &self.mint_a,
+                self.subscriber_ata_a.to_account_info(),
+                self.subscription.to_account_info(),
+                signer_seeds,
+                unearned,
+            )?;
+        }
+
+        // the subscription account is closed by `close = subscriber`
+        close_vault(
+            self.token_program.to_account_info(),
+            self.vault.to_account_info(),
+            self.subscriber.to_account_info(),
+            self.subscription.to_account_info(),
+            signer_seeds,
+        )
+    }
+}
@@ -1,25 +1,25 @@
-use anchor_lang::{prelude::*, system_program::Transfer};
+use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken, 
     token_interface::{
         TokenAccount,
         TokenInterface,
         Mint,
-        TransferChecked,
-        transfer_checked,
-        close_account,
-        CloseAccount
     }
 };
 
 
-use crate::state::EscrowState;
+use crate::constants::{SESSION_APPROVE_RELEASE, SESSION_SET_RECEIVER};
+use crate::error::ErrorCode;
+use crate::state::{check_authority, EscrowState, SessionToken};
+use crate::utils::{close_vault, transfer_from_vault};
 
 
 #[derive(Accounts)]
 pub struct Release<'info> {
     #[account(mut)]
     pub maker: Signer<'info>,   // remains payer
+    #[account(mut)]
     pub receiver: Signer<'info>,
     #[account( 
         mut,
@@ -52,7 +52,7 @@ pub struct Release<'info> {
     pub vault: InterfaceAccount<'info, TokenAccount>,
 
     pub mint_a: InterfaceAccount<'info, Mint>,
-    pub token_program: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
@@ -60,52 +60,46 @@ pub struct Release<'info> {
 impl<'info> Release<'info> {
     pub fn release(&mut self) -> Result<()> {
         // Seed derivation for PDA authority
+        let seed_bytes = self.escrow.seed.to_le_bytes();
         let seeds = &[
             b"escrow",
             self.escrow.maker.as_ref(),
-            &self.escrow.seed.to_le_bytes(),
+            seed_bytes.as_ref(),
             &[self.escrow.bump],
         ];
         let signer_seeds = &[&seeds[..]];
 
         // Transfer tokens from the vault PDA to the receiver ATA
-        let cpi_accounts = TransferChecked {
-            from:      self.vault.to_account_info(),
-            mint:      self.mint_a.to_account_info(),
-            to:        self.receiver_ata.to_account_info(),
-            authority: self.escrow.to_account_info(),
-        };
-        let cpi_ctx = CpiContext::new_with_signer(
+        transfer_from_vault(
             self.token_program.to_account_info(),
-            cpi_accounts,
+            self.vault.to_account_info(),
+            &self.mint_a,
+            self.receiver_ata.to_account_info(),
+            self.escrow.to_account_info(),
             signer_seeds,
-        );
-        transfer_checked(cpi_ctx, self.escrow.amount, self.mint_a.decimals)?;
-        Ok(())
+            self.escrow.amount,
+        )
     }
 
     pub fn close(&mut self) -> Result<()> {
         // Close the vault and escrow PDA just as before
+        let seed_bytes = self.escrow.seed.to_le_bytes();
         let seeds = &[
             b"escrow",
             self.escrow.maker.as_ref(),
-            &self.escrow.seed.to_le_bytes(),
+            seed_bytes.as_ref(),
             &[self.escrow.bump],
         ];
         let signer_seeds = &[&seeds[..]];
 
         // Close vault
-        let cpi_close = CloseAccount {
-            account:     self.vault.to_account_info(),
-            destination: self.receiver.to_account_info(),
-            authority:   self.escrow.to_account_info(),
-        };
-        let cpi_ctx = CpiContext::new_with_signer(
+        close_vault(
             self.token_program.to_account_info(),
-            cpi_close,
+            self.vault.to_account_info(),
+            self.receiver.to_account_info(),
+            self.escrow.to_account_info(),
             signer_seeds,
-        );
-        close_account(cpi_ctx)?;
+        )?;
 
         // Escrow account is closed automatically by `close = maker` in struct
         Ok(())
@@ -113,23 +107,138 @@ impl<'info> Release<'info> {
 }
 
 
+// the receiver's side of a release the maker (or one of its session keys) approved with `approve_release`
 #[derive(Accounts)]
-pub struct SetReceiver<'info> {
+pub struct ClaimRelease<'info> {
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,   // gets the escrow rent back
+    #[account(mut)]
+    pub receiver: Signer<'info>,   // pays for its own ATA
     #[account(
         mut,
+        close = maker,
         has_one = maker,
-        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        has_one = receiver,
+        seeds = [b"escrow", escrow.maker.as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump)]
+    pub escrow: Account<'info, EscrowState>,
+    #[account(
+        init_if_needed,
+        payer = receiver,
+        associated_token::mint = mint_a,
+        associated_token::authority = receiver,
+        associated_token::token_program = token_program
+    )]
+    pub receiver_ata: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClaimRelease<'info> {
+    pub fn claim(&mut self) -> Result<()> {
+        require!(self.escrow.release_approved, ErrorCode::ReleaseNotApproved);
+
+        let seed_bytes = self.escrow.seed.to_le_bytes();
+        let seeds = &[
+            b"escrow",
+            self.escrow.maker.as_ref(),
+            seed_bytes.as_ref(),
+            &[self.escrow.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        transfer_from_vault(
+            self.token_program.to_account_info(),
+            self.vault.to_account_info(),
+            &self.mint_a,
+            self.receiver_ata.to_account_info(),
+            self.escrow.to_account_info(),
+            signer_seeds,
+            self.escrow.amount,
+        )?;
+
+        // same as `release`, the vault rent goes to the receiver and the escrow rent to the maker
+        close_vault(
+            self.token_program.to_account_info(),
+            self.vault.to_account_info(),
+            self.receiver.to_account_info(),
+            self.escrow.to_account_info(),
+            signer_seeds,
+        )
+    }
+}
+
+
+#[derive(Accounts)]
+pub struct SetReceiver<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.maker.as_ref(), escrow.seed.to_le_bytes().as_ref()],
         bump = escrow.bump,
     )]
     pub escrow: Account<'info, EscrowState>,
-    pub maker: Signer<'info>,
+    // the maker, or a session key the maker delegated to
+    pub authority: Signer<'info>,
+    pub session: Option<Account<'info, SessionToken>>,
     /// CHECK: Only storing the pubkey
     pub receiver: UncheckedAccount<'info>,
 }
 
 impl<'info> SetReceiver<'info> {
     pub fn set_receiver(&mut self) -> Result<()> {
+        check_authority(
+            &self.escrow.maker,
+            self.authority.key,
+            self.session.as_deref(),
+            SESSION_SET_RECEIVER,
+            &self.escrow.key(),
+            self.escrow.amount,
+        )?;
+
+        // an approval was given for the previous receiver, it must not carry over to a new one
         self.escrow.receiver = self.receiver.key();
+        self.escrow.release_approved = false;
+        Ok(())
+    }
+}
+
+
+#[derive(Accounts)]
+pub struct ApproveRelease<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.maker.as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, EscrowState>,
+    // the maker, or a session key the maker delegated to
+    pub authority: Signer<'info>,
+    pub session: Option<Account<'info, SessionToken>>,
+}
+
+impl<'info> ApproveRelease<'info> {
+    // lets the receiver call `claim_release` without the maker signing anything
+    pub fn approve_release(&mut self) -> Result<()> {
+        check_authority(
+            &self.escrow.maker,
+            self.authority.key,
+            self.session.as_deref(),
+            SESSION_APPROVE_RELEASE,
+            &self.escrow.key(),
+            self.escrow.amount,
+        )?;
+        require!(self.escrow.receiver != Pubkey::default(), ErrorCode::ReceiverNotSet);
+
+        self.escrow.release_approved = true;
         Ok(())
     }
 }
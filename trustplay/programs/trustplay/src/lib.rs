@@ -2,6 +2,7 @@ pub mod constants;
 pub mod error;
 pub mod instructions;
 pub mod state;
+pub mod utils;
 
 use anchor_lang::prelude::*;
 
@@ -18,4 +19,108 @@ pub mod trustplay {
     pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
         initialize::handler(ctx)
     }
+
+    // the escrow seed is the maker's `MakerProfile::escrow_count`
+    // `crank_tip` lamports are set aside for whoever cranks the escrow once it expires
+    pub fn make(ctx: Context<Make>, amount: u64, expires_at: i64, crank_tip: u64) -> Result<()> {
+        ctx.accounts.make(amount, expires_at, crank_tip, &ctx.bumps)?;
+        ctx.accounts.deposit(amount)
+    }
+
+    pub fn set_receiver(ctx: Context<SetReceiver>) -> Result<()> {
+        ctx.accounts.set_receiver()
+    }
+
+    pub fn approve_release(ctx: Context<ApproveRelease>) -> Result<()> {
+        ctx.accounts.approve_release()
+    }
+
+    pub fn release(ctx: Context<Release>) -> Result<()> {
+        ctx.accounts.release()?;
+        ctx.accounts.close()
+    }
+
+    // receiver-signed release, only once `approve_release` went through
+    pub fn claim_release(ctx: Context<ClaimRelease>) -> Result<()> {
+        ctx.accounts.claim()
+    }
+
+    pub fn refund(ctx: Context<Refund>) -> Result<()> {
+        ctx.accounts.refund()?;
+        ctx.accounts.close()
+    }
+
+    pub fn crank_expired(ctx: Context<CrankExpired>) -> Result<()> {
+        ctx.accounts.refund_expired()?;
+        ctx.accounts.pay_tip()
+    }
+
+    // with `skip_failed` an escrow whose accounts don't check out is skipped and reported in `BatchSettled`,
+    // the checks run before any CPI so one bad escrow never aborts the rest of the batch
+    pub fn batch_release<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchSettle<'info>>,
+        skip_failed: bool,
+    ) -> Result<()> {
+        batch::settle_batch(ctx, Settlement::Release, skip_failed)
+    }
+
+    pub fn batch_refund<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchSettle<'info>>,
+        skip_failed: bool,
+    ) -> Result<()> {
+        batch::settle_batch(ctx, Settlement::Refund, skip_failed)
+    }
+
+    pub fn create_invoice(
+        ctx: Context<CreateInvoice>,
+        seed: u64,
+        amount: u64,
+        memo_hash: [u8; 32],
+        due_date: i64,
+    ) -> Result<()> {
+        ctx.accounts.create_invoice(seed, amount, memo_hash, due_date, &ctx.bumps)
+    }
+
+    pub fn pay_invoice(ctx: Context<PayInvoice>) -> Result<()> {
+        ctx.accounts.pay_invoice(&ctx.bumps)
+    }
+
+    // the invoice account is closed to the receiver by the account constraints
+    pub fn cancel_invoice(_ctx: Context<CancelInvoice>) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn subscribe(
+        ctx: Context<Subscribe>,
+        seed: u64,
+        amount_per_period: u64,
+        period: i64,
+        periods: u64,
+    ) -> Result<()> {
+        ctx.accounts.subscribe(seed, amount_per_period, period, periods, &ctx.bumps)?;
+        ctx.accounts.deposit()
+    }
+
+    pub fn collect(ctx: Context<Collect>) -> Result<()> {
+        ctx.accounts.collect()
+    }
+
+    pub fn cancel_subscription(ctx: Context<CancelSubscription>) -> Result<()> {
+        ctx.accounts.cancel()
+    }
+
+    pub fn create_session(
+        ctx: Context<CreateSession>,
+        permissions: u8,
+        escrows: Vec<Pubkey>,
+        max_amount: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        ctx.accounts.create_session(permissions, escrows, max_amount, expires_at, &ctx.bumps)
+    }
+
+    // the session account is closed to the authority by the account constraints
+    pub fn revoke_session(_ctx: Context<RevokeSession>) -> Result<()> {
+        Ok(())
+    }
 }
@@ -8,5 +8,8 @@ pub struct EscrowState{
   pub mint_a : Pubkey,
   pub amount : u64 , 
   pub bump : u8,
-  pub receiver : Pubkey
+  pub receiver : Pubkey,
+  pub release_approved : bool,
+  pub expires_at : i64 ,      // 0 means the escrow never expires
+  pub crank_tip : u64         // lamports held on top of the rent for whoever cranks it once expired
 }
\ No newline at end of file
@@ -0,0 +1,15 @@
+use anchor_lang::prelude::*;
+
+// a payment request opened by the receiver, paying it turns it into an EscrowState
+#[account]
+#[derive(InitSpace)]
+pub struct InvoiceState {
+  pub seed : u64 ,
+  pub receiver : Pubkey,
+  pub payer : Pubkey,
+  pub mint_a : Pubkey,
+  pub amount : u64 ,
+  pub memo_hash : [u8; 32],
+  pub due_date : i64,           // 0 means no due date, the invoice can't be paid after it
+  pub bump : u8
+}
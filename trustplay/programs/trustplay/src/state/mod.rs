@@ -1,2 +1,14 @@
 pub mod escrow;
-pub use escrow::*;
\ No newline at end of file
+pub use escrow::*;
+
+pub mod invoice;
+pub use invoice::*;
+
+pub mod subscription;
+pub use subscription::*;
+
+pub mod session;
+pub use session::*;
+
+pub mod profile;
+pub use profile::*;
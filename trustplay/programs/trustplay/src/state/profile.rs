@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+
+// one per maker, `escrow_count` is the seed of the maker's next escrow
+// so every escrow of a maker sits at seeds 0..escrow_count
+#[account]
+#[derive(InitSpace)]
+pub struct MakerProfile {
+  pub maker : Pubkey,
+  pub escrow_count : u64 ,
+  pub bump : u8
+}
+
+impl MakerProfile {
+  // hands out the next seed, the first call also records who owns the profile
+  pub fn next_seed(&mut self, maker: Pubkey, bump: u8) -> Result<u64> {
+    if self.maker == Pubkey::default() {
+      self.maker = maker;
+      self.bump = bump;
+    }
+    let seed = self.escrow_count;
+    self.escrow_count = seed.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+    Ok(seed)
+  }
+}
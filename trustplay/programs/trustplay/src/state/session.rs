@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::MAX_SESSION_ESCROWS;
+use crate::error::ErrorCode;
+
+// lets `session_key` act for `authority` on a scoped set of instructions until `expires_at`
+#[account]
+#[derive(InitSpace)]
+pub struct SessionToken {
+  pub authority : Pubkey,
+  pub session_key : Pubkey,
+  pub permissions : u8,
+  #[max_len(MAX_SESSION_ESCROWS)]
+  pub escrows : Vec<Pubkey>,
+  pub max_amount : u64 ,
+  pub expires_at : i64 ,
+  pub bump : u8
+}
+
+impl SessionToken {
+  pub fn check(&self, signer: &Pubkey, permission: u8, escrow: &Pubkey, amount: u64) -> Result<()> {
+    require_keys_eq!(self.session_key, *signer, ErrorCode::Unauthorized);
+    require!(Clock::get()?.unix_timestamp < self.expires_at, ErrorCode::SessionExpired);
+    require!(self.permissions & permission == permission, ErrorCode::SessionNotPermitted);
+    require!(self.escrows.is_empty() || self.escrows.contains(escrow), ErrorCode::SessionNotPermitted);
+    require!(amount <= self.max_amount, ErrorCode::SessionNotPermitted);
+    Ok(())
+  }
+}
+
+// accepts either `owner` itself or a live session key that `owner` delegated to
+pub fn check_authority(
+  owner: &Pubkey,
+  signer: &Pubkey,
+  session: Option<&SessionToken>,
+  permission: u8,
+  escrow: &Pubkey,
+  amount: u64,
+) -> Result<()> {
+  if signer == owner {
+    return Ok(());
+  }
+  let session = session.ok_or(ErrorCode::Unauthorized)?;
+  require_keys_eq!(session.authority, *owner, ErrorCode::Unauthorized);
+  session.check(signer, permission, escrow, amount)
+}
@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+// a vault pre-funded with `periods` payments that the receiver pulls one period at a time
+#[account]
+#[derive(InitSpace)]
+pub struct SubscriptionState {
+  pub seed : u64 ,
+  pub subscriber : Pubkey,
+  pub receiver : Pubkey,
+  pub mint_a : Pubkey,
+  pub amount_per_period : u64 ,
+  pub period : i64 ,            // interval length in seconds
+  pub periods : u64 ,           // number of periods funded up front
+  pub periods_claimed : u64 ,
+  pub start_ts : i64 ,
+  pub bump : u8
+}
+
+impl SubscriptionState {
+  // periods that have fully elapsed since `start_ts`, capped at what was funded
+  pub fn periods_earned(&self, now: i64) -> u64 {
+    if now <= self.start_ts {
+      return 0;
+    }
+    let elapsed = ((now - self.start_ts) / self.period) as u64;
+    elapsed.min(self.periods)
+  }
+
+  // earned periods the receiver has not pulled yet
+  pub fn periods_due(&self, now: i64) -> u64 {
+    self.periods_earned(now).saturating_sub(self.periods_claimed)
+  }
+}
@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use anchor_spl::token_interface::{
+    close_account, get_mint_extension_data, transfer_checked, CloseAccount, Mint, TransferChecked,
+};
+
+use crate::error::ErrorCode;
+
+// moves `amount` out of a vault ATA whose authority is a program PDA
+pub fn transfer_from_vault<'info>(
+    token_program: AccountInfo<'info>,
+    vault: AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    let cpi_accounts = TransferChecked {
+        from: vault,
+        mint: mint.to_account_info(),
+        to,
+        authority,
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program, cpi_accounts, signer_seeds);
+
+    transfer_checked(cpi_ctx, amount, mint.decimals)
+}
+
+// closes an (empty) vault ATA owned by a program PDA and sends its rent to `destination`
+pub fn close_vault<'info>(
+    token_program: AccountInfo<'info>,
+    vault: AccountInfo<'info>,
+    destination: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let cpi_accounts = CloseAccount {
+        account: vault,
+        destination,
+        authority,
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program, cpi_accounts, signer_seeds);
+
+    close_account(cpi_ctx)
+}
+
+// how much has to be sent for `net` to arrive after the mint's transfer fee,
+// mints without the token 2022 transfer fee extension just return `net`
+pub fn amount_with_transfer_fee(mint: &InterfaceAccount<Mint>, net: u64) -> Result<u64> {
+    let mint_info = mint.to_account_info();
+    let Ok(fee_config) = get_mint_extension_data::<TransferFeeConfig>(&mint_info) else {
+        return Ok(net);
+    };
+
+    let fee = fee_config
+        .calculate_inverse_epoch_fee(Clock::get()?.epoch, net)
+        .ok_or(ErrorCode::MathOverflow)?;
+    net.checked_add(fee).ok_or(error!(ErrorCode::MathOverflow))
+}
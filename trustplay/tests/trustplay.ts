@@ -1,8 +1,20 @@
 import * as anchor from "@project-serum/anchor";
 import { Program, BN, IdlAccounts } from "@project-serum/anchor";
-import { PublicKey, Keypair, SystemProgram } from "@solana/web3.js";
+import { PublicKey, Keypair, SystemProgram, Transaction, sendAndConfirmTransaction } from "@solana/web3.js";
 import { EscrowDemo } from "../target/types/escrow_demo";
-import { TOKEN_PROGRAM_ID, getAssociatedTokenAddress, createMint, mintTo, createAssociatedTokenAccount } from "@solana/spl-token";
+import {
+  ExtensionType,
+  TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
+  getAssociatedTokenAddress,
+  getAssociatedTokenAddressSync,
+  getMintLen,
+  createInitializeMintInstruction,
+  createInitializeTransferFeeConfigInstruction,
+  createMint,
+  mintTo,
+  createAssociatedTokenAccount,
+} from "@solana/spl-token";
 
 describe("escrow-demo", () => {
   // Configure the client to use the local cluster.
@@ -19,6 +31,12 @@ describe("escrow-demo", () => {
   let makerAtaA: PublicKey;
   let receiverAta: PublicKey;
 
+  // escrows are seeded by the maker's profile counter
+  const [makerProfile] = PublicKey.findProgramAddressSync(
+    [Buffer.from("profile"), provider.wallet.publicKey.toBuffer()],
+    program.programId
+  );
+
   // PDA bump and address
   let escrowPda: PublicKey;
   let escrowBump: number;
@@ -29,6 +47,58 @@ describe("escrow-demo", () => {
   // Amount to escrow
   const amount = new BN(1_000);
 
+  // the validator's clock, which is what the program compares deadlines against
+  const chainTime = async () => {
+    const slot = await provider.connection.getSlot();
+    return (await provider.connection.getBlockTime(slot)) as number;
+  };
+  const waitUntil = async (timestamp: number) => {
+    while ((await chainTime()) <= timestamp) {
+      await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+  };
+
+  // asserts that `promise` fails with the program error `code`
+  const expectError = async (promise: Promise<unknown>, code: string) => {
+    try {
+      await promise;
+    } catch (err) {
+      anchor.assert.include(String(err), code);
+      return;
+    }
+    anchor.assert.fail(`expected ${code}`);
+  };
+
+  // a new escrow of the maker, at the next seed of its profile
+  const makeEscrow = async (escrowAmount: number, expiresAt = 0, crankTip = 0) => {
+    const profile = await program.account.makerProfile.fetch(makerProfile);
+    const [escrow] = PublicKey.findProgramAddressSync(
+      [Buffer.from("escrow"), maker.publicKey.toBuffer(), profile.escrowCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const vault = await getAssociatedTokenAddress(mintA, escrow, true);
+    await program.methods
+      .make(new BN(escrowAmount), new BN(expiresAt), new BN(crankTip))
+      .accounts({
+        maker: maker.publicKey,
+        mintA,
+        makerAtaA,
+        makerProfile,
+        escrow,
+        vault,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+      })
+      .rpc();
+    return { escrow, vault };
+  };
+
+  const airdrop = async (to: PublicKey) => {
+    const sig = await provider.connection.requestAirdrop(to, 1e9);
+    await provider.connection.confirmTransaction(sig);
+  };
+
   before(async () => {
     // Airdrop to receiver so they can pay for ATA & transactions
     await provider.connection.requestAirdrop(receiver.publicKey, 1e9);
@@ -65,7 +135,7 @@ describe("escrow-demo", () => {
   it("1. make & deposit", async () => {
     // Derive the escrow PDA
     [escrowPda, escrowBump] = await PublicKey.findProgramAddress(
-      [Buffer.from("escrow"), maker.publicKey.toBuffer(), Buffer.from(new BN(42).toArray("le", 8))],
+      [Buffer.from("escrow"), maker.publicKey.toBuffer(), Buffer.from(new BN(0).toArray("le", 8))],
       program.programId
     );
 
@@ -74,11 +144,12 @@ describe("escrow-demo", () => {
 
     // 1a. Call `make`
     await program.methods
-      .make(new BN(42), amount)
+      .make(amount, new BN(0), new BN(0))
       .accounts({
         maker: maker.publicKey,
         mintA,
         makerAtaA,
+        makerProfile,
         escrow: escrowPda,
         vault: vaultAta,
         systemProgram: SystemProgram.programId,
@@ -92,7 +163,7 @@ describe("escrow-demo", () => {
     const state = await program.account.escrowState.fetch(escrowPda);
     anchor.assert.ok(state.maker.equals(maker.publicKey));
     anchor.assert.ok(state.mintA.equals(mintA));
-    anchor.assert.ok(state.seed.eq(new BN(42)));
+    anchor.assert.ok(state.seed.eq(new BN(0)));
     anchor.assert.ok(state.amount.eq(amount));
     anchor.assert.ok(state.bump === escrowBump);
     anchor.assert.ok(state.receiver.equals(PublicKey.default));
@@ -129,7 +200,8 @@ describe("escrow-demo", () => {
       .setReceiver()
       .accounts({
         escrow: escrowPda,
-        maker: maker.publicKey,
+        authority: maker.publicKey,
+        session: null,
         receiver: receiver.publicKey,
       })
       .signers([receiver])  // receiver isn't a signer in constraints, but TS SDK requires pass
@@ -170,4 +242,481 @@ describe("escrow-demo", () => {
     const recvBal = await provider.connection.getTokenAccountBalance(receiverAta);
     anchor.assert.equal(recvBal.value.uiAmount, 1_000);
   });
+  it("4. invoice: create & pay", async () => {
+    const invoiceSeed = new BN(7);
+    const [invoicePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("invoice"), receiver.publicKey.toBuffer(), invoiceSeed.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    // the maker's second escrow
+    const [invoiceEscrow] = PublicKey.findProgramAddressSync(
+      [Buffer.from("escrow"), maker.publicKey.toBuffer(), new BN(1).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const invoiceVault = await getAssociatedTokenAddress(mintA, invoiceEscrow, true);
+
+    // the receiver asks the maker for 500 tokens
+    await program.methods
+      .createInvoice(invoiceSeed, new BN(500), Array(32).fill(0), new BN(0))
+      .accounts({
+        receiver: receiver.publicKey,
+        payer: maker.publicKey,
+        mintA,
+        invoice: invoicePda,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([receiver])
+      .rpc();
+
+    await program.methods
+      .payInvoice()
+      .accounts({
+        payer: maker.publicKey,
+        receiver: receiver.publicKey,
+        mintA,
+        payerAtaA: makerAtaA,
+        invoice: invoicePda,
+        payerProfile: makerProfile,
+        escrow: invoiceEscrow,
+        vault: invoiceVault,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+      })
+      .rpc();
+
+    // the invoice is gone and the escrow already names the receiver
+    anchor.assert.ok((await provider.connection.getAccountInfo(invoicePda)) === null);
+    const state = await program.account.escrowState.fetch(invoiceEscrow);
+    anchor.assert.ok(state.receiver.equals(receiver.publicKey));
+    anchor.assert.ok(state.amount.eq(new BN(500)));
+  });
+
+  it("5. invoice: due date", async () => {
+    const invoiceAt = (seed: BN) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("invoice"), receiver.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+    const create = (seed: BN, dueDate: number) =>
+      program.methods
+        .createInvoice(seed, new BN(500), Array(32).fill(0), new BN(dueDate))
+        .accounts({
+          receiver: receiver.publicKey,
+          payer: maker.publicKey,
+          mintA,
+          invoice: invoiceAt(seed),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([receiver])
+        .rpc();
+
+    const now = await chainTime();
+    await expectError(create(new BN(8), now - 1), "InvalidDueDate");
+
+    // due in a few seconds, then left to lapse
+    const dueDate = now + 3;
+    await create(new BN(8), dueDate);
+    await waitUntil(dueDate);
+
+    const profile = await program.account.makerProfile.fetch(makerProfile);
+    const [escrow] = PublicKey.findProgramAddressSync(
+      [Buffer.from("escrow"), maker.publicKey.toBuffer(), profile.escrowCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    await expectError(
+      program.methods
+        .payInvoice()
+        .accounts({
+          payer: maker.publicKey,
+          receiver: receiver.publicKey,
+          mintA,
+          payerAtaA: makerAtaA,
+          invoice: invoiceAt(new BN(8)),
+          payerProfile: makerProfile,
+          escrow,
+          vault: await getAssociatedTokenAddress(mintA, escrow, true),
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        })
+        .rpc(),
+      "InvoiceOverdue"
+    );
+
+    // the receiver can still take the rent back
+    await program.methods
+      .cancelInvoice()
+      .accounts({ receiver: receiver.publicKey, invoice: invoiceAt(new BN(8)) })
+      .signers([receiver])
+      .rpc();
+    anchor.assert.ok((await provider.connection.getAccountInfo(invoiceAt(new BN(8)))) === null);
+  });
+
+  it("6. subscription: collect & cancel", async () => {
+    const seed = new BN(1);
+    const period = 6;
+    const [subscription] = PublicKey.findProgramAddressSync(
+      [Buffer.from("subscription"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const subscriptionVault = await getAssociatedTokenAddress(mintA, subscription, true);
+    const tokenBalance = async (ata: PublicKey) =>
+      Number((await provider.connection.getTokenAccountBalance(ata)).value.amount);
+
+    // 5 periods of 100 paid up front
+    await program.methods
+      .subscribe(seed, new BN(100), new BN(period), new BN(5))
+      .accounts({
+        subscriber: maker.publicKey,
+        receiver: receiver.publicKey,
+        mintA,
+        subscriberAtaA: makerAtaA,
+        subscription,
+        vault: subscriptionVault,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+      })
+      .rpc();
+    anchor.assert.equal(await tokenBalance(subscriptionVault), 500);
+
+    const collect = () =>
+      program.methods
+        .collect()
+        .accounts({
+          receiver: receiver.publicKey,
+          mintA,
+          subscription,
+          vault: subscriptionVault,
+          receiverAta,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        })
+        .signers([receiver])
+        .rpc();
+
+    // nothing is earned until a whole interval has passed
+    await expectError(collect(), "NothingToCollect");
+
+    const { startTs } = await program.account.subscriptionState.fetch(subscription);
+    await waitUntil(startTs.toNumber() + period);
+
+    const received = await tokenBalance(receiverAta);
+    await collect();
+    anchor.assert.equal(await tokenBalance(receiverAta), received + 100);
+    // and only one period per interval
+    await expectError(collect(), "NothingToCollect");
+
+    // cancelling within the second interval hands back the 4 periods not earned yet
+    const subscriberBefore = await tokenBalance(makerAtaA);
+    await program.methods
+      .cancelSubscription()
+      .accounts({
+        subscriber: maker.publicKey,
+        receiver: receiver.publicKey,
+        mintA,
+        subscriberAtaA: makerAtaA,
+        subscription,
+        vault: subscriptionVault,
+        receiverAta,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+      })
+      .rpc();
+
+    anchor.assert.equal(await tokenBalance(makerAtaA), subscriberBefore + 400);
+    anchor.assert.equal(await tokenBalance(receiverAta), received + 100);
+    anchor.assert.ok((await provider.connection.getAccountInfo(subscription)) === null);
+    anchor.assert.ok((await provider.connection.getAccountInfo(subscriptionVault)) === null);
+  });
+
+  it("7. session keys", async () => {
+    const SESSION_SET_RECEIVER = 1;
+    const SESSION_APPROVE_RELEASE = 2;
+    const sessionAt = (key: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("session"), maker.publicKey.toBuffer(), key.toBuffer()],
+        program.programId
+      )[0];
+    const createSession = (key: PublicKey, permissions: number, escrows: PublicKey[], expiresAt: number) =>
+      program.methods
+        .createSession(permissions, escrows, new BN(1_000), new BN(expiresAt))
+        .accounts({
+          authority: maker.publicKey,
+          sessionKey: key,
+          session: sessionAt(key),
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    const setReceiver = (escrow: PublicKey, key: Keypair, to: PublicKey) =>
+      program.methods
+        .setReceiver()
+        .accounts({ escrow, authority: key.publicKey, session: sessionAt(key.publicKey), receiver: to })
+        .signers([key])
+        .rpc();
+
+    const { escrow, vault } = await makeEscrow(300);
+    const { escrow: otherEscrow } = await makeEscrow(300);
+
+    // a game client key scoped to one escrow
+    const clientKey = Keypair.generate();
+    const now = await chainTime();
+    await createSession(clientKey.publicKey, SESSION_SET_RECEIVER | SESSION_APPROVE_RELEASE, [escrow], now + 3_600);
+
+    await setReceiver(escrow, clientKey, receiver.publicKey);
+    await program.methods
+      .approveRelease()
+      .accounts({ escrow, authority: clientKey.publicKey, session: sessionAt(clientKey.publicKey) })
+      .signers([clientKey])
+      .rpc();
+    let state = await program.account.escrowState.fetch(escrow);
+    anchor.assert.ok(state.receiver.equals(receiver.publicKey));
+    anchor.assert.ok(state.releaseApproved);
+
+    // outside its escrow list the key has no rights
+    await expectError(setReceiver(otherEscrow, clientKey, receiver.publicKey), "SessionNotPermitted");
+
+    // a key that may only set receivers can't redirect an approved release to itself
+    const receiverOnlyKey = Keypair.generate();
+    await createSession(receiverOnlyKey.publicKey, SESSION_SET_RECEIVER, [], now + 3_600);
+    await setReceiver(escrow, receiverOnlyKey, receiverOnlyKey.publicKey);
+    state = await program.account.escrowState.fetch(escrow);
+    anchor.assert.ok(state.receiver.equals(receiverOnlyKey.publicKey));
+    anchor.assert.ok(!state.releaseApproved);
+
+    // the maker doesn't sign a claim, the receiver pays its own fee and ATA
+    await airdrop(receiverOnlyKey.publicKey);
+    const claimRelease = (to: Keypair) =>
+      program.methods
+        .claimRelease()
+        .accounts({
+          maker: maker.publicKey,
+          receiver: to.publicKey,
+          escrow,
+          receiverAta: getAssociatedTokenAddressSync(mintA, to.publicKey),
+          vault,
+          mintA,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .transaction()
+        .then((tx) => {
+          tx.feePayer = to.publicKey;
+          return sendAndConfirmTransaction(provider.connection, tx, [to]);
+        });
+    await expectError(claimRelease(receiverOnlyKey), "ReleaseNotApproved");
+
+    // once the scoped key points it back and approves again , the receiver claims without the maker
+    await setReceiver(escrow, clientKey, receiver.publicKey);
+    await program.methods
+      .approveRelease()
+      .accounts({ escrow, authority: clientKey.publicKey, session: sessionAt(clientKey.publicKey) })
+      .signers([clientKey])
+      .rpc();
+    const receiverBefore = Number((await provider.connection.getTokenAccountBalance(receiverAta)).value.amount);
+    await claimRelease(receiver);
+    anchor.assert.equal(
+      Number((await provider.connection.getTokenAccountBalance(receiverAta)).value.amount),
+      receiverBefore + 300
+    );
+    anchor.assert.ok((await provider.connection.getAccountInfo(escrow)) === null);
+
+    // and a lapsed session is refused
+    const shortKey = Keypair.generate();
+    const expiresAt = (await chainTime()) + 3;
+    await createSession(shortKey.publicKey, SESSION_SET_RECEIVER, [], expiresAt);
+    await waitUntil(expiresAt);
+    await expectError(setReceiver(otherEscrow, shortKey, receiver.publicKey), "SessionExpired");
+  });
+
+  it("8. batch release & refund", async () => {
+    const tokenBalance = async (ata: PublicKey) =>
+      Number((await provider.connection.getTokenAccountBalance(ata)).value.amount);
+    const meta = (pubkey: PublicKey) => ({ pubkey, isWritable: true, isSigner: false });
+    const batch = (release: boolean, skipFailed: boolean, tuples: PublicKey[][]) =>
+      (release ? program.methods.batchRelease(skipFailed) : program.methods.batchRefund(skipFailed))
+        .accounts({ maker: maker.publicKey, mintA, tokenProgram: TOKEN_PROGRAM_ID })
+        .remainingAccounts(tuples.flat().map(meta))
+        .rpc();
+
+    const first = await makeEscrow(100);
+    const second = await makeEscrow(200);
+    // never given a receiver, so it can't be released
+    const unaccepted = await makeEscrow(300);
+    for (const { escrow } of [first, second]) {
+      await program.methods
+        .setReceiver()
+        .accounts({ escrow, authority: maker.publicKey, session: null, receiver: receiver.publicKey })
+        .rpc();
+    }
+
+    // releases carry the receiver too , it gets the vault rent like a single release
+    const goodTuples = [first, second].map(({ escrow, vault }) => [escrow, vault, receiverAta, receiver.publicKey]);
+    const badTuple = [unaccepted.escrow, unaccepted.vault, receiverAta, receiver.publicKey];
+
+    // without skip_failed one bad tuple aborts the whole batch
+    await expectError(batch(true, false, [...goodTuples, badTuple]), "ReceiverNotSet");
+    anchor.assert.ok((await provider.connection.getAccountInfo(first.escrow)) !== null);
+
+    // with it the good escrows settle and the event reports the skipped one
+    let settled: any;
+    const listener = program.addEventListener("BatchSettled", (event) => (settled = event));
+    const received = await tokenBalance(receiverAta);
+    const receiverLamports = await provider.connection.getBalance(receiver.publicKey);
+    const vaultRent = await provider.connection.getBalance(first.vault);
+    await batch(true, true, [...goodTuples, badTuple]);
+    for (let i = 0; i < 20 && !settled; i++) {
+      await new Promise((resolve) => setTimeout(resolve, 250));
+    }
+    await program.removeEventListener(listener);
+
+    anchor.assert.equal(await tokenBalance(receiverAta), received + 300);
+    anchor.assert.equal(await provider.connection.getBalance(receiver.publicKey), receiverLamports + 2 * vaultRent);
+    anchor.assert.ok((await provider.connection.getAccountInfo(first.escrow)) === null);
+    anchor.assert.ok((await provider.connection.getAccountInfo(second.vault)) === null);
+    anchor.assert.ok(settled.released);
+    anchor.assert.deepEqual(settled.results, [true, true, false]);
+    anchor.assert.ok(settled.escrows[2].equals(unaccepted.escrow));
+
+    // refunds go back to the maker, whether or not a receiver was set
+    const another = await makeEscrow(400);
+    const makerBefore = await tokenBalance(makerAtaA);
+    await batch(false, false, [unaccepted, another].map(({ escrow, vault }) => [escrow, vault, makerAtaA]));
+    anchor.assert.equal(await tokenBalance(makerAtaA), makerBefore + 700);
+    anchor.assert.ok((await provider.connection.getAccountInfo(unaccepted.escrow)) === null);
+    anchor.assert.ok((await provider.connection.getAccountInfo(another.escrow)) === null);
+  });
+
+  it("9. crank expired escrows", async () => {
+    const tip = 50_000;
+    const cranker = Keypair.generate();
+    // funded so the tip doesn't leave it below rent exemption
+    await airdrop(cranker.publicKey);
+
+    const crank = ({ escrow, vault }: { escrow: PublicKey; vault: PublicKey }) =>
+      program.methods
+        .crankExpired()
+        .accounts({
+          cranker: cranker.publicKey,
+          maker: maker.publicKey,
+          mintA,
+          makerAtaA,
+          escrow,
+          vault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([cranker])
+        .rpc();
+
+    await expectError(makeEscrow(100, (await chainTime()) - 1, tip), "InvalidExpiry");
+
+    const expiresAt = (await chainTime()) + 3;
+    const expiring = await makeEscrow(100, expiresAt, tip);
+    const accepted = await makeEscrow(100, expiresAt, tip);
+    await program.methods
+      .setReceiver()
+      .accounts({ escrow: accepted.escrow, authority: maker.publicKey, session: null, receiver: receiver.publicKey })
+      .rpc();
+
+    await expectError(crank(expiring), "EscrowNotExpired");
+    await waitUntil(expiresAt);
+    // once a receiver is set the escrow is no longer the cranker's to reap
+    await expectError(crank(accepted), "EscrowAlreadyAccepted");
+
+    const makerTokens = Number((await provider.connection.getTokenAccountBalance(makerAtaA)).value.amount);
+    const crankerLamports = await provider.connection.getBalance(cranker.publicKey);
+    await crank(expiring);
+
+    anchor.assert.equal(
+      Number((await provider.connection.getTokenAccountBalance(makerAtaA)).value.amount),
+      makerTokens + 100
+    );
+    // the provider pays the fee, so the cranker's balance moves by exactly the tip
+    anchor.assert.equal(await provider.connection.getBalance(cranker.publicKey), crankerLamports + tip);
+    anchor.assert.ok((await provider.connection.getAccountInfo(expiring.escrow)) === null);
+    anchor.assert.ok((await provider.connection.getAccountInfo(expiring.vault)) === null);
+  });
+
+  it("10. subscription: transfer fee mint", async () => {
+    // token 2022 mint with a 1% transfer fee
+    const feeMint = Keypair.generate();
+    const space = getMintLen([ExtensionType.TransferFeeConfig]);
+    await sendAndConfirmTransaction(
+      provider.connection,
+      new Transaction().add(
+        SystemProgram.createAccount({
+          fromPubkey: maker.publicKey,
+          newAccountPubkey: feeMint.publicKey,
+          space,
+          lamports: await provider.connection.getMinimumBalanceForRentExemption(space),
+          programId: TOKEN_2022_PROGRAM_ID,
+        }),
+        createInitializeTransferFeeConfigInstruction(
+          feeMint.publicKey, maker.publicKey, maker.publicKey, 100, BigInt(1_000_000), TOKEN_2022_PROGRAM_ID
+        ),
+        createInitializeMintInstruction(feeMint.publicKey, 6, maker.publicKey, null, TOKEN_2022_PROGRAM_ID)
+      ),
+      [maker, feeMint]
+    );
+    const subscriberAta = await createAssociatedTokenAccount(
+      provider.connection, maker, feeMint.publicKey, maker.publicKey, undefined, TOKEN_2022_PROGRAM_ID
+    );
+    await mintTo(provider.connection, maker, feeMint.publicKey, subscriberAta, maker, 10_000, [], undefined, TOKEN_2022_PROGRAM_ID);
+
+    const seed = new BN(2);
+    const period = 2;
+    const [subscription] = PublicKey.findProgramAddressSync(
+      [Buffer.from("subscription"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const vault = await getAssociatedTokenAddress(feeMint.publicKey, subscription, true, TOKEN_2022_PROGRAM_ID);
+    const feeReceiverAta = await getAssociatedTokenAddress(feeMint.publicKey, receiver.publicKey, false, TOKEN_2022_PROGRAM_ID);
+    const tokenBalance = async (ata: PublicKey) =>
+      Number((await provider.connection.getTokenAccountBalance(ata)).value.amount);
+
+    // 2 periods of 100 , the deposit is grossed up so the vault still holds every period in full
+    await program.methods
+      .subscribe(seed, new BN(100), new BN(period), new BN(2))
+      .accounts({
+        subscriber: maker.publicKey,
+        receiver: receiver.publicKey,
+        mintA: feeMint.publicKey,
+        subscriberAtaA: subscriberAta,
+        subscription,
+        vault,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+      })
+      .rpc();
+    anchor.assert.equal(await tokenBalance(vault), 200);
+    // 203 sent , 3 of it withheld as the fee
+    anchor.assert.equal(10_000 - (await tokenBalance(subscriberAta)), 203);
+
+    const { startTs } = await program.account.subscriptionState.fetch(subscription);
+    await waitUntil(startTs.toNumber() + 2 * period);
+
+    // the last period can still be pulled
+    await program.methods
+      .collect()
+      .accounts({
+        receiver: receiver.publicKey,
+        mintA: feeMint.publicKey,
+        subscription,
+        vault,
+        receiverAta: feeReceiverAta,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+      })
+      .signers([receiver])
+      .rpc();
+    anchor.assert.equal(await tokenBalance(vault), 0);
+    // the fee on the way out is the receiver's
+    anchor.assert.equal(await tokenBalance(feeReceiverAta), 198);
+  });
 });